@@ -9,7 +9,7 @@ fn main() {
   website.configuration.delay = 15; // Defaults to 250 ms
   website.configuration.concurrency = 10; // Defaults to number of cpus available
   website.configuration.user_agent = "SpiderBot".into(); // Defaults to spider/x.y.z, where x.y.z is the library version
-  website.crawl();
+  website.crawl().unwrap();
 
   for page in website.get_pages() {
     println!("- {}", page.get_url());
@@ -1,9 +1,14 @@
 use num_cpus;
+use reqwest::header::HeaderMap;
+use reqwest::tls::Version as TlsVersion;
 use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+#[cfg(not(any(feature = "regex", feature = "glob")))]
 use url::Url;
 
 /// Specify whether the crwaler should follow links (ALL, SITE, DOMAIN or NONE)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum FollowLinks
 {
     /// Follow all links
@@ -26,20 +31,48 @@ impl Default for FollowLinks
     }
 }
 
+/// Which links are dropped when the frontier exceeds `max_frontier`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum FrontierOverflow
+{
+    /// Drop the most recently discovered links
+    #[default]
+    DropNewest,
+    /// Drop the earliest discovered links
+    DropOldest
+}
+
+/// When the crawl state is saved to `checkpoint_file`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckpointTrigger
+{
+    /// Save after every number of pages visited
+    Pages(usize),
+    /// Save once this duration passed since the last save
+    Interval(Duration)
+}
+
 /// Structure to configure `Website` crawler
 /// ```rust
 /// use spider::website::Website;
 /// let mut website: Website = Website::new("https://choosealicense.com");
 /// website.configuration.blacklist_url.push("https://choosealicense.com/licenses/".to_string());
 /// website.configuration.respect_robots_txt = true;
-/// website.crawl();
+/// website.crawl().unwrap();
 /// ```
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Configuration {
     /// Respect robots.txt file and not scrape not allowed files.
     pub respect_robots_txt: bool,
-    /// List of pages to not crawl. [optional: regex pattern matching]
+    /// List of pages to not crawl. [optional: regex or glob pattern matching]
+    #[cfg(not(any(feature = "regex", feature = "glob")))]
     pub blacklist_url: Vec<Url>,
+    /// List of glob patterns of the pages to not crawl, `*` matching any characters and `?` one.
+    #[cfg(all(feature = "glob", not(feature = "regex")))]
+    pub blacklist_url: Vec<String>,
+    /// List of regex patterns of the pages to not crawl, compiled when the crawl starts.
+    #[cfg(feature = "regex")]
+    pub blacklist_url: Vec<String>,
     /// Follow links?
     pub follow_links: FollowLinks,
     /// User-Agent
@@ -47,7 +80,149 @@ pub struct Configuration {
     /// Polite crawling delay in milli seconds.
     pub delay: u64,
     /// How many request can be run simultaneously.
-    pub concurrency: usize
+    pub concurrency: usize,
+    /// Host header sent with every request, used to reach a site through its IP.
+    pub host_header: Option<String>,
+    /// Sort query parameters of links so the order of parameters does not matter for dedup.
+    pub sort_query_params: bool,
+    /// Retry responses with status 200 and an empty body.
+    pub retry_empty_body: bool,
+    /// Maximum retries of a request failing with a transport error, a 5xx or a 429 status.
+    pub max_retries: usize,
+    /// Capture the TLS certificate of every crawled https host, with the protocol and the cipher
+    /// negotiated by a separate handshake with the host. The handshake is skipped with a `proxy`.
+    pub tls_info: bool,
+    /// How many pages can be parsed for links simultaneously, unbounded by default.
+    pub parse_concurrency: Option<usize>,
+    /// Newline-delimited file the discovered frontier is appended to after every round.
+    pub frontier_file: Option<PathBuf>,
+    /// Newline-delimited file the visited URL are appended to after every round.
+    pub visited_file: Option<PathBuf>,
+    /// Lowercase the path of links for servers with case-insensitive paths, queries are kept as is.
+    pub case_insensitive_paths: bool,
+    /// Allow visited URL to be crawled again once this duration passed since their last visit.
+    pub revisit_after: Option<Duration>,
+    /// Element attributes holding pagination links to follow, like `data-next-url` or `data-load-more`.
+    pub pagination_attrs: Vec<String>,
+    /// Apply unicode NFC normalization to link paths so composed and decomposed forms dedup.
+    pub unicode_normalize: bool,
+    /// Only enqueue links discovered within this duration from the crawl start, enqueued links are still crawled.
+    pub discovery_window: Option<Duration>,
+    /// Stop the crawl on the first transport error or 4xx/5xx response and return it.
+    pub fail_fast: bool,
+    /// Bound of a single page request, the page is recorded with a timeout error past it.
+    pub page_timeout: Option<Duration>,
+    /// Seed every page of numbered pagination links like `?page=1..50` from the highest page number found.
+    pub expand_numbered_pagination: bool,
+    /// Maximum depth of links followed from the start URL at depth 0.
+    pub max_depth: Option<usize>,
+    /// Maximum number of pages visited by a crawl.
+    pub max_pages: Option<usize>,
+    /// Parse the links of responses served with `Content-Disposition: attachment`, skipped by default as downloads.
+    pub parse_attachments: bool,
+    /// Schedule the links by interleaving deep dives and breadth exploration, from `0.0` visiting
+    /// the shallowest links first to `1.0` visiting the deepest links first. Links are fetched in
+    /// batches of `concurrency` links when set.
+    pub depth_breadth_ratio: Option<f64>,
+    /// Timeout of the client requests. [default: 15 seconds]
+    pub request_timeout: Option<Duration>,
+    /// Base delay before retrying a request, doubled on each retry with a random `retry_jitter`.
+    pub retry_backoff: Duration,
+    /// Directory caching the pages served with an `ETag` or `Last-Modified` validator, revalidated with conditional requests.
+    pub cache_dir: Option<PathBuf>,
+    /// Request the headers with a HEAD first and only fetch the pages with an html `Content-Type`.
+    pub head_precheck: bool,
+    /// MIME types of the responses parsed like `text/html`, the body of other responses is discarded.
+    pub allowed_content_types: Option<Vec<String>>,
+    /// Run `crawl` with async requests on a single thread runtime instead of a thread per request,
+    /// `concurrency` bounds the requests in flight.
+    pub async_runtime: bool,
+    /// Find the links of the `<noscript>` fallback content. [default: true]
+    pub scan_noscript: bool,
+    /// Maximum number of links waiting to be visited, extra links are dropped by `frontier_overflow`.
+    pub max_frontier: Option<usize>,
+    /// Links dropped when the frontier exceeds `max_frontier`. [default: DropNewest]
+    pub frontier_overflow: FrontierOverflow,
+    /// Maximum number of simultaneous connections to a resolved IP address, shared by the hosts
+    /// on that address. Applies to the threaded crawls, unbounded by default.
+    pub max_conns_per_ip: Option<usize>,
+    /// Rewrite links on an `m.` mobile subdomain to the desktop host, `m.example.com` to `example.com`.
+    pub canonicalize_mobile: bool,
+    /// Query parameters removed from links, a trailing `*` matches a prefix.
+    /// [default: utm_*, fbclid, gclid]
+    pub strip_query_params: Vec<String>,
+    /// Probability of a fetch keeping its request headers, response headers and body for
+    /// `Website::captures`, none are kept by default.
+    pub capture_sample: Option<f64>,
+    /// Skip the anchors with a `rel="nofollow"` attribute when extracting links.
+    pub respect_nofollow: bool,
+    /// Follow no link of the pages with a `<meta name="robots">` `nofollow` or `none` directive.
+    pub respect_meta_robots: bool,
+    /// Follow the URL literals of inline `onclick` handlers, like `location.href='/x'`.
+    /// Off by default as the extraction is heuristic.
+    pub scan_onclick: bool,
+    /// Save the crawl state to `checkpoint_file` periodically, checked after every round.
+    pub checkpoint_every: Option<CheckpointTrigger>,
+    /// JSON file the crawl state is saved to with `checkpoint_every`.
+    pub checkpoint_file: Option<PathBuf>,
+    /// Also follow the `<area href>`, `<iframe src>` and `<link rel="next|prev">` elements.
+    pub include_link_elements: bool,
+    /// Do not follow the links of pages whose SimHash fingerprint is within this Hamming distance
+    /// of a page already visited, like pages differing only by a timestamp.
+    pub near_dup_threshold: Option<u32>,
+    /// Seed the crawl with the pages listed in `/sitemap.xml` and the `Sitemap:` entries of robots.txt,
+    /// following sitemap indexes and gzip compressed sitemaps.
+    pub crawl_sitemap: bool,
+    /// Hosts a redirect may lead to from another host, cross-host redirects to other hosts are not
+    /// followed and recorded with `Website::out_of_scope_redirects`. All redirects are followed by default.
+    pub allowed_redirect_hosts: Option<Vec<String>>,
+    /// Fetch the linked `.pdf` documents and follow their hyperlinks, PDFs are only parsed with
+    /// the `pdf` feature.
+    pub parse_pdfs: bool,
+    /// Minimum TLS version of the https connections, servers using an older version are refused.
+    pub min_tls_version: Option<TlsVersion>,
+    /// List of the only pages to crawl when not empty, the blacklist still applies. [optional: regex or glob pattern matching]
+    #[cfg(not(any(feature = "regex", feature = "glob")))]
+    pub whitelist_url: Vec<Url>,
+    /// List of glob patterns of the only pages to crawl when not empty.
+    #[cfg(all(feature = "glob", not(feature = "regex")))]
+    pub whitelist_url: Vec<String>,
+    /// List of regex patterns of the only pages to crawl when not empty, compiled when the crawl starts.
+    #[cfg(feature = "regex")]
+    pub whitelist_url: Vec<String>,
+    /// Maximum number of links followed per depth, the first links discovered at each depth are
+    /// followed to crawl a sample of the website.
+    pub max_breadth_per_level: Option<usize>,
+    /// Abort the crawl with a `fanout exceeded` error when the pages average more new links than
+    /// this over the last 10 pages, guarding against crawl traps and too wide scopes.
+    pub max_fanout: Option<f64>,
+    /// Maximum number of requests in flight to a single host, the links of a host at its limit
+    /// wait for a request to end.
+    pub max_concurrent_per_host: Option<usize>,
+    /// Maximum number of requests per second of all the workers, each fetch waits for its turn.
+    pub requests_per_second: Option<f64>,
+    /// Share of the `retry_backoff` base added as random jitter to each retry delay so the
+    /// requests failing together don't retry together, `0.0` retries at the exact delays. [default: 1.0]
+    pub retry_jitter: f64,
+    /// Range of milliseconds the polite delay of each request is drawn from uniformly instead of
+    /// the constant `delay`.
+    pub delay_jitter: Option<(u64, u64)>,
+    /// Headers sent with every request, overriding the default headers of the http clients. The
+    /// cached responses with a `Vary` header are stored per value of the headers they vary on.
+    pub headers: HeaderMap,
+    /// Proxy all the requests go through, an `http://`, `https://` or `socks5://` URL. A malformed
+    /// proxy stops the crawl with an `InvalidProxy` error before any request.
+    pub proxy: Option<String>,
+    /// Username and password of the proxy basic authentication.
+    pub proxy_auth: Option<(String, String)>,
+    /// Record the `http:` links and resources of the `https:` pages visited, listed with
+    /// `Website::mixed_content`.
+    pub detect_mixed_content: bool,
+    /// Store the cookies set by the responses and send them back on the next requests to the
+    /// same host, keeping sessions across the pages of a crawl.
+    pub enable_cookies: bool,
+    /// Accept the invalid and self-signed certificates of https hosts, for staging or internal sites.
+    pub accept_invalid_certs: bool
 }
 
 impl Configuration {
@@ -67,6 +242,9 @@ impl Configuration {
             user_agent: concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).into(),
             delay: 250,
             concurrency,
+            scan_noscript: true,
+            strip_query_params: vec!["utm_*".into(), "fbclid".into(), "gclid".into()],
+            retry_jitter: 1.0,
             ..Default::default()
         }
     }
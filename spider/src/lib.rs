@@ -36,6 +36,16 @@ extern crate tokio;
 extern crate url;
 extern crate hashbrown;
 extern crate log;
+extern crate percent_encoding;
+extern crate flate2;
+extern crate httpdate;
+extern crate publicsuffix;
+extern crate rand;
+extern crate rustls;
+extern crate serde;
+extern crate serde_json;
+extern crate unicode_normalization;
+extern crate x509_parser;
 #[macro_use]
 extern crate lazy_static;
 
@@ -43,6 +53,8 @@ extern crate lazy_static;
 pub mod configuration;
 /// A page scraped.
 pub mod page;
+/// Sitemap parsing and fetching.
+pub mod sitemap;
 /// Application utils.
 pub mod utils;
 /// A website to crawl.
@@ -51,26 +63,72 @@ pub mod website;
 #[cfg(feature = "regex")]
 /// Black list checking url exist with Regex.
 pub mod black_list {
-    use regex::Regex;
-    /// check if link exist in blacklists with regex.
-    pub fn contains(blacklist_url: &Vec<String>, link: &String) -> bool {
-        for pattern in blacklist_url {
-            let re = Regex::new(pattern).unwrap();
-            if re.is_match(link) {
-                return true;
-            }
-        }
+    use regex::RegexSet;
+    use url::Url;
+    /// compile the regex patterns once, failing on the first malformed pattern.
+    pub fn compile(blacklist_url: &Vec<String>) -> Result<RegexSet, regex::Error> {
+        RegexSet::new(blacklist_url)
+    }
+    /// check if link match a compiled blacklist regex.
+    pub fn contains(blacklist: &RegexSet, link: &Url) -> bool {
+        blacklist.is_match(link.as_str())
+    }
+}
 
-        return false;
+#[cfg(all(feature = "glob", not(feature = "regex")))]
+/// Black list checking url match with glob patterns.
+pub mod black_list {
+    use crate::utils::glob_match;
+    use url::Url;
+    /// check if link match a glob pattern of the blacklist, `*` matching any characters and `?` one.
+    pub fn contains(blacklist_url: &[String], link: &Url) -> bool {
+        blacklist_url.iter().any(|pattern| glob_match(pattern, link.as_str()))
     }
 }
 
-#[cfg(not(feature = "regex"))]
+#[cfg(not(any(feature = "regex", feature = "glob")))]
 /// Black list checking url exist.
 pub mod black_list {
+    use crate::utils::canonical_trailing_slash;
     use url::Url;
-    /// check if link exist in blacklists.
+    /// check if link exist in blacklists, `/page/` and `/page` are the same page.
     pub fn contains(blacklist_url: &Vec<Url>, link: &Url) -> bool {
-        blacklist_url.contains(&link)
+        let link = canonical_trailing_slash(link);
+        blacklist_url.iter().any(|url| canonical_trailing_slash(url) == link)
+    }
+}
+
+#[cfg(feature = "regex")]
+/// White list checking url match with Regex.
+pub mod white_list {
+    use regex::RegexSet;
+    use url::Url;
+    /// check if link is allowed by the compiled whitelist regexes, an empty whitelist allows every link.
+    pub fn allows(whitelist: &RegexSet, link: &Url) -> bool {
+        whitelist.is_empty() || whitelist.is_match(link.as_str())
+    }
+}
+
+#[cfg(all(feature = "glob", not(feature = "regex")))]
+/// White list checking url match with glob patterns.
+pub mod white_list {
+    use crate::utils::glob_match;
+    use url::Url;
+    /// check if link match a glob pattern of the whitelist, an empty whitelist allows every link.
+    pub fn allows(whitelist_url: &[String], link: &Url) -> bool {
+        whitelist_url.is_empty() || whitelist_url.iter().any(|pattern| glob_match(pattern, link.as_str()))
+    }
+}
+
+#[cfg(not(any(feature = "regex", feature = "glob")))]
+/// White list checking url exist.
+pub mod white_list {
+    use crate::utils::canonical_trailing_slash;
+    use url::Url;
+    /// check if link is allowed by the whitelist, an empty whitelist allows every link. `/page/`
+    /// and `/page` are the same page.
+    pub fn allows(whitelist_url: &[Url], link: &Url) -> bool {
+        let link = canonical_trailing_slash(link);
+        whitelist_url.is_empty() || whitelist_url.iter().any(|url| canonical_trailing_slash(url) == link)
     }
 }
@@ -1,8 +1,11 @@
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
 use url::Url;
-use crate::utils::{fetch_page_html};
+use crate::configuration::{Configuration, FollowLinks};
+use crate::utils::{canonicalize, fetch_page, fetch_page_async, is_fetchable, mime_essence, simhash, PageResponse, TlsInfo};
+use reqwest::header::{HeaderMap, CONTENT_LANGUAGE, CONTENT_TYPE};
 use reqwest::blocking::{Client};
-use hashbrown::HashSet;
+use hashbrown::{HashMap, HashSet};
+use reqwest::StatusCode;
 
 /// Represent a page visited. This page contains HTML scraped with [scraper](https://crates.io/crates/scraper).
 #[derive(Debug, Clone)]
@@ -11,8 +14,18 @@ pub struct Page {
     url: String,
     /// HTML parsed with [scraper](https://crates.io/crates/scraper) lib. The html is not stored and only used to parse links.
     html: String,
-    /// Base absolute url for domain.
-    base: Url
+    /// Base absolute url links are resolved against, the page URL or its `<base href>`.
+    base: Url,
+    /// Error description when the page could not be fetched.
+    error: Option<String>,
+    /// TLS details of the connection when captured.
+    tls_info: Option<TlsInfo>,
+    /// HTTP status code of the response.
+    status_code: Option<StatusCode>,
+    /// HTTP headers of the response.
+    headers: HeaderMap,
+    /// Hyperlinks of a PDF document page when parsing PDFs.
+    pdf_links: Vec<String>
 }
 
 /// Macro to get all media selectors that should be ignored for link gathering.
@@ -37,17 +50,56 @@ lazy_static! {
 impl Page {
     /// Instantiate a new page and start to scrape it.
     pub fn new(url: &Url, client: &Client) -> Self {
-        let html = fetch_page_html(&url, &client); // TODO: remove heavy cpu / network from new
+        Page::new_with_configuration(url, client, &Configuration::default())
+    }
+
+    /// Instantiate a new page and start to scrape it with the fetch options of the configuration.
+    pub fn new_with_configuration(url: &Url, client: &Client, configuration: &Configuration) -> Self {
+        // TODO: remove heavy cpu / network from new
+        Page::from_response(url, fetch_page(&url, &client, configuration))
+    }
 
-        Page::build(url, &html)
+    /// Instantiate a new page and start to scrape it asynchronously with the fetch options of the configuration.
+    pub async fn new_with_configuration_async(url: &Url, client: &reqwest::Client, configuration: &Configuration) -> Self {
+        Page::from_response(url, fetch_page_async(url, client, configuration).await)
     }
 
-    /// Instanciate a new page without scraping it (used for testing purposes).
+    /// Instantiate a page from the outcome of its request.
+    fn from_response(url: &Url, response: Result<PageResponse, String>) -> Self {
+        match response {
+            Ok(res) => {
+                let mut page = Page::build(url, &res.body);
+                // relative links resolve against the URL the request was redirected to
+                if let Some(redirect_url) = res.redirect_url {
+                    page.base = base_href(&redirect_url, &res.body).unwrap_or(redirect_url);
+                }
+                page.tls_info = res.tls_info;
+                page.status_code = Some(res.status);
+                page.headers = res.headers;
+                page.pdf_links = res.pdf_links;
+                page
+            },
+            Err(error) => {
+                let mut page = Page::build(url, "");
+                page.status_code = None;
+                page.error = Some(error);
+                page
+            }
+        }
+    }
+
+    /// Instanciate a new page without scraping it (used for testing purposes), the status is `200 OK`.
+    /// Relative links resolve against the `<base href>` of the document when it declares one.
     pub fn build(url: &Url, html: &str) -> Self {
         Self {
             url: url.to_string(),
             html: html.to_string(),
-            base: url.to_owned()
+            base: base_href(url, html).unwrap_or_else(|| url.to_owned()),
+            error: None,
+            tls_info: None,
+            status_code: Some(StatusCode::OK),
+            headers: HeaderMap::new(),
+            pdf_links: Vec::new()
         }
     }
 
@@ -61,11 +113,47 @@ impl Page {
         &self.html
     }
 
+    /// Error getter for page, set when the request failed.
+    pub fn get_error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+
+    /// HTTP status code getter for page, `None` when the request failed before a response.
+    pub fn get_status_code(&self) -> Option<StatusCode> {
+        self.status_code
+    }
+
+    /// HTTP headers getter for page, empty when the request failed or for local files.
+    pub fn get_headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// MIME type of the `Content-Type` header without its parameters, like `text/html`.
+    pub fn content_type(&self) -> Option<String> {
+        self.headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(mime_essence)
+    }
+
+    /// TLS details getter for page, set when the client captures them.
+    pub fn get_tls_info(&self) -> Option<&TlsInfo> {
+        self.tls_info.as_ref()
+    }
+
     /// HTML returned from Scraper.
     fn parse_html(&self) -> Html {
         Html::parse_document(&self.html)
     }
 
+    /// SimHash fingerprint of the text of the page to detect near-duplicate pages.
+    pub fn simhash(&self) -> u64 {
+        let html = self.parse_html();
+        let text: String = html.root_element().text().collect::<Vec<_>>().join(" ");
+
+        simhash(&text)
+    }
+
     /// Clear the html for the page.
     pub fn clear_html(&mut self) {
         self.html.clear();
@@ -99,23 +187,467 @@ impl Page {
 
     /// Find all href links and return them using CSS selectors.
     pub fn links(&self) -> HashSet<Url> {
+        self.anchor_links(false)
+    }
+
+    /// Find all href links, skipping the `rel="nofollow"` anchors when `respect_nofollow` is set.
+    fn anchor_links(&self, respect_nofollow: bool) -> HashSet<Url> {
         let selector = self.get_page_selectors(&self.url);
         let html = self.parse_html();
-        
+        let mut links: HashSet<Url> = html.select(&selector)
+            .filter(|a| !(respect_nofollow && is_nofollow(a)))
+            .filter_map(|a| self.abs_path(a.value().attr("href").unwrap_or_default()))
+            .collect();
+
+        // local sites link documents relative to the file path
+        if self.base.scheme() == "file" {
+            let relative_selector = Selector::parse(&format!(
+                r##"a[href]:not([href*=":"]):not([href^="#"]){}"##,
+                *MEDIA_IGNORE_SELECTOR
+            ))
+            .unwrap();
+
+            links.extend(
+                html.select(&relative_selector)
+                    .filter(|a| !(respect_nofollow && is_nofollow(a)))
+                    .filter_map(|a| self.abs_path(a.value().attr("href").unwrap_or_default())),
+            );
+        }
+
+        links
+    }
+
+    /// Find every `a[href]` regardless of scope or media type, with its raw and resolved form.
+    pub fn all_hrefs(&self) -> Vec<(String, Option<Url>)> {
+        let selector = Selector::parse("a[href]").unwrap();
+        let html = self.parse_html();
+
+        html.select(&selector)
+            .map(|a| {
+                let href = a.value().attr("href").unwrap_or_default().to_string();
+                let resolved = self.base.join(href.trim()).ok().map(|mut url| {
+                    url.set_fragment(None);
+                    url
+                });
+
+                (href, resolved)
+            })
+            .collect()
+    }
+
+    /// Find all anchors with a `download` attribute paired with their suggested filename.
+    pub fn download_links(&self) -> Vec<(Url, Option<String>)> {
+        let selector = Selector::parse("a[href][download]").unwrap();
+        let html = self.parse_html();
+
+        html.select(&selector)
+            .filter_map(|a| {
+                let filename = a
+                    .value()
+                    .attr("download")
+                    .map(|f| f.trim().to_string())
+                    .filter(|f| !f.is_empty());
+
+                Some((self.abs_path(a.value().attr("href").unwrap_or_default())?, filename))
+            })
+            .collect()
+    }
+
+    /// Text of the page `<title>`.
+    pub fn title(&self) -> Option<String> {
+        let selector = Selector::parse("title").unwrap();
+        let html = self.parse_html();
+
+        html.select(&selector)
+            .next()
+            .map(|title| title.text().collect::<String>().trim().to_string())
+            .filter(|title| !title.is_empty())
+    }
+
+    /// Language declared by the page with `<html lang="...">`, else the first language of its
+    /// `Content-Language` header.
+    pub fn language(&self) -> Option<String> {
+        let selector = Selector::parse("html[lang]").unwrap();
+        let html = self.parse_html();
+
+        html.select(&selector)
+            .next()
+            .and_then(|element| element.value().attr("lang"))
+            .map(|lang| lang.trim().to_string())
+            .filter(|lang| !lang.is_empty())
+            .or_else(|| {
+                self.headers
+                    .get(CONTENT_LANGUAGE)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.split(',').next())
+                    .map(|lang| lang.trim().to_string())
+                    .filter(|lang| !lang.is_empty())
+            })
+    }
+
+    /// Directives of the `<meta name="robots">` tags in lowercase, like `noindex` or `nofollow`.
+    pub fn meta_robots(&self) -> Vec<String> {
+        let selector = Selector::parse("meta[name][content]").unwrap();
+        let html = self.parse_html();
+
+        html.select(&selector)
+            .filter(|meta| meta.value().attr("name").is_some_and(|name| name.trim().eq_ignore_ascii_case("robots")))
+            .flat_map(|meta| {
+                meta.value()
+                    .attr("content")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|directive| directive.trim().to_lowercase())
+                    .filter(|directive| !directive.is_empty())
+                    .collect::<Vec<String>>()
+            })
+            .collect()
+    }
+
+    /// return `true` if the meta robots directives ask not to follow the links of the page.
+    pub fn is_meta_nofollow(&self) -> bool {
+        self.meta_robots().iter().any(|d| d == "nofollow" || d == "none")
+    }
+
+    /// return `true` if the meta robots directives ask not to index the page.
+    pub fn is_meta_noindex(&self) -> bool {
+        self.meta_robots().iter().any(|d| d == "noindex" || d == "none")
+    }
+
+    /// Structured data of every `<script type="application/ld+json">` block, malformed blocks are skipped.
+    pub fn json_ld(&self) -> Vec<serde_json::Value> {
+        let selector = Selector::parse(r#"script[type="application/ld+json"]"#).unwrap();
+        let html = self.parse_html();
+
+        html.select(&selector)
+            .filter_map(|script| serde_json::from_str(&script.text().collect::<String>()).ok())
+            .collect()
+    }
+
+    /// Find all `url(...)` references in inline `<style>` blocks and `style` attributes.
+    pub fn css_urls(&self) -> HashSet<Url> {
+        let selector = Selector::parse("style, [style]").unwrap();
+        let html = self.parse_html();
+        let mut urls = HashSet::new();
+
+        for element in html.select(&selector) {
+            let css = match element.value().attr("style") {
+                Some(style) => style.to_string(),
+                None => element.text().collect::<String>(),
+            };
+
+            for reference in css_url_references(&css) {
+                if let Some(url) = self.abs_path(reference) {
+                    urls.insert(url);
+                }
+            }
+        }
+
+        urls
+    }
+
+    /// Find all links using the extraction options of the configuration.
+    pub fn links_with_configuration(&self, configuration: &Configuration) -> HashSet<Url> {
+        if configuration.respect_meta_robots && self.is_meta_nofollow() {
+            return HashSet::new();
+        }
+        let mut links = self.anchor_links(configuration.respect_nofollow);
+
+        if !configuration.pagination_attrs.is_empty() {
+            links.extend(self.attribute_links(&configuration.pagination_attrs));
+        }
+        if matches!(configuration.follow_links, FollowLinks::SAMEDOMAIN | FollowLinks::SUBDOMAINS) {
+            links.extend(self.external_anchor_links(configuration.respect_nofollow));
+        }
+        if configuration.scan_noscript {
+            links.extend(self.noscript_links());
+        }
+        if configuration.scan_onclick {
+            links.extend(self.onclick_links());
+        }
+        if configuration.include_link_elements {
+            links.extend(self.link_element_links());
+        }
+        if configuration.parse_pdfs {
+            links.extend(self.pdf_links());
+        }
+        if configuration.expand_numbered_pagination {
+            let pages = numbered_pagination(&links);
+            links.extend(pages);
+        }
+
+        links
+    }
+
+    /// Find the links of the `<noscript>` elements, their content is raw text when parsing with scripting.
+    pub fn noscript_links(&self) -> HashSet<Url> {
+        let selector = Selector::parse("noscript").unwrap();
+        let html = self.parse_html();
+
+        html.select(&selector)
+            .flat_map(|noscript| {
+                let mut fallback = Page::build(&self.base, &noscript.text().collect::<String>());
+                fallback.url = self.url.clone();
+                fallback.links()
+            })
+            .collect()
+    }
+
+    /// Find the absolute web page links to any host.
+    pub fn external_links(&self) -> HashSet<Url> {
+        self.external_anchor_links(false)
+    }
+
+    /// Find the absolute web page links to any host, skipping the `rel="nofollow"` anchors when
+    /// `respect_nofollow` is set.
+    fn external_anchor_links(&self, respect_nofollow: bool) -> HashSet<Url> {
+        let selector = Selector::parse(&format!(
+            r#"a[href^="http://"]{0}, a[href^="https://"]{0}"#,
+            *MEDIA_IGNORE_SELECTOR
+        ))
+        .unwrap();
+        let html = self.parse_html();
+
+        html.select(&selector)
+            .filter(|a| !(respect_nofollow && is_nofollow(a)))
+            .filter_map(|a| self.abs_path(a.value().attr("href").unwrap_or_default().trim()))
+            .collect()
+    }
+
+    /// Find the URL of the `<area href>` image maps, `<iframe src>` frames and
+    /// `<link rel="next">` or `<link rel="prev">` pagination elements.
+    pub fn link_element_links(&self) -> HashSet<Url> {
+        let selector = Selector::parse("area[href], iframe[src], link[rel][href]").unwrap();
+        let html = self.parse_html();
+
+        html.select(&selector)
+            .filter_map(|element| {
+                let element = element.value();
+                match element.name() {
+                    "iframe" => element.attr("src"),
+                    "link" => element
+                        .attr("rel")
+                        .filter(|rel| {
+                            rel.split_ascii_whitespace()
+                                .any(|r| r.eq_ignore_ascii_case("next") || r.eq_ignore_ascii_case("prev"))
+                        })
+                        .and(element.attr("href")),
+                    _ => element.attr("href"),
+                }
+            })
+            .map(str::trim)
+            .filter(|href| !href.is_empty())
+            .filter_map(|href| self.abs_path(href))
+            .collect()
+    }
+
+    /// Find the links to PDF documents and, for a PDF document page, its hyperlinks.
+    pub fn pdf_links(&self) -> HashSet<Url> {
+        let selector = Selector::parse(r#"a[href$=".pdf"], a[href$=".PDF"]"#).unwrap();
+        let html = self.parse_html();
+        let mut links: HashSet<Url> = html.select(&selector)
+            .filter_map(|a| self.abs_path(a.value().attr("href").unwrap_or_default().trim()))
+            .collect();
+
+        links.extend(self.pdf_links.iter().filter_map(|href| self.abs_path(href)));
+
+        links
+    }
+
+    /// Find the `http:` links and resources, like images, scripts, frames or stylesheets, of an
+    /// `https:` page. Other pages have no mixed content.
+    pub fn mixed_content(&self) -> HashSet<Url> {
+        if !self.url.starts_with("https:") {
+            return HashSet::new();
+        }
+        let selector = Selector::parse(
+            "a[href], area[href], link[href], img[src], script[src], iframe[src], source[src], \
+             audio[src], video[src], embed[src], object[data], form[action]",
+        )
+        .unwrap();
+        let html = self.parse_html();
+
+        html.select(&selector)
+            .filter_map(|element| {
+                let element = element.value();
+                element
+                    .attr("href")
+                    .or_else(|| element.attr("src"))
+                    .or_else(|| element.attr("data"))
+                    .or_else(|| element.attr("action"))
+            })
+            .filter_map(|href| self.abs_path(href.trim()))
+            .filter(|url| url.scheme() == "http")
+            .collect()
+    }
+
+    /// Find the URL held by the given attributes of any element, like `data-next-url`.
+    pub fn attribute_links(&self, attributes: &[String]) -> HashSet<Url> {
+        let html = self.parse_html();
+        let mut links = HashSet::new();
+
+        for attribute in attributes {
+            if let Ok(selector) = Selector::parse(&format!("[{}]", attribute)) {
+                for element in html.select(&selector) {
+                    let href = element.value().attr(attribute).map(str::trim).filter(|h| !h.is_empty());
+                    if let Some(link) = href.and_then(|href| self.abs_path(href)) {
+                        links.insert(link);
+                    }
+                }
+            }
+        }
+
+        links
+    }
+
+    /// Find the URL literals navigated to by inline `onclick` handlers, like
+    /// `onclick="location.href='/x'"` or `onclick="window.open('/x')"`.
+    pub fn onclick_links(&self) -> HashSet<Url> {
+        let selector = Selector::parse("[onclick]").unwrap();
+        let html = self.parse_html();
+
         html.select(&selector)
-            .map(|a| self.abs_path(a.value().attr("href").unwrap_or_default()))
+            .flat_map(|element| string_literals(element.value().attr("onclick").unwrap_or_default()))
+            .filter(|literal| is_url_literal(literal))
+            .filter_map(|literal| self.abs_path(&literal))
             .collect()
     }
 
-    /// Convert a URL to its absolute path without any fragments or params.
-    fn abs_path(&self, href: &str) -> Url {
-        let mut joined = self.base.join(href).unwrap_or(Url::parse(&self.url.to_string()).expect("Invalid page URL"));
+    /// Convert a URL to its absolute path without any fragments or params, `None` for hrefs
+    /// failing to resolve or not fetchable like `wss:` or `tel:` links.
+    fn abs_path(&self, href: &str) -> Option<Url> {
+        let mut joined = self.base.join(href).ok().filter(is_fetchable)?;
 
         joined.set_fragment(None);
 
-        joined
+        Some(canonicalize(&joined))
     }
 }
+/// The URL declared by the `<base href>` element of a document, relative to the page URL.
+fn base_href(url: &Url, html: &str) -> Option<Url> {
+    if !html.contains("<base") && !html.contains("<BASE") {
+        return None;
+    }
+    let selector = Selector::parse("base[href]").unwrap();
+    let document = Html::parse_document(html);
+    let href = document.select(&selector).next()?.value().attr("href")?.trim();
+
+    url.join(href)
+        .ok()
+        .filter(|base| matches!(base.scheme(), "http" | "https" | "file"))
+}
+
+/// The quoted string literals of a script snippet.
+fn string_literals(script: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut chars = script.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\'' || c == '"' {
+            let mut literal = String::new();
+            while let Some(next) = chars.next() {
+                match next {
+                    '\\' => literal.extend(chars.next()),
+                    _ if next == c => break,
+                    _ => literal.push(next),
+                }
+            }
+            literals.push(literal);
+        }
+    }
+
+    literals
+}
+
+/// return `true` if a script string literal looks like a link to a page.
+fn is_url_literal(literal: &str) -> bool {
+    let literal = literal.trim();
+    let lower = literal.to_ascii_lowercase();
+    let path = lower.split('?').next().unwrap_or_default();
+
+    !literal.is_empty()
+        && !literal.contains(char::is_whitespace)
+        && (["/", "./", "../", "http://", "https://"].iter().any(|prefix| lower.starts_with(prefix))
+            || [".html", ".htm", ".php", ".asp", ".aspx"].iter().any(|ext| path.ends_with(ext)))
+}
+
+/// return `true` if the `rel` attribute of the anchor contains `nofollow`.
+fn is_nofollow(anchor: &ElementRef) -> bool {
+    anchor
+        .value()
+        .attr("rel")
+        .is_some_and(|rel| rel.split_ascii_whitespace().any(|r| r.eq_ignore_ascii_case("nofollow")))
+}
+
+/// Query parameters holding a page number in pagination links.
+const PAGINATION_PARAMS: [&str; 4] = ["page", "p", "pg", "paged"];
+/// Highest page number expanded from a pagination link.
+const MAX_PAGINATION_PAGES: u32 = 1000;
+
+/// Expand the pagination links to every page up to the highest page number found,
+/// e.g. `?page=5` seeds `?page=1` to `?page=5` for the same path and other parameters.
+fn numbered_pagination(links: &HashSet<Url>) -> HashSet<Url> {
+    let mut last_pages: HashMap<(Url, String), u32> = HashMap::new();
+
+    for link in links {
+        for (name, value) in link.query_pairs() {
+            if !PAGINATION_PARAMS.contains(&name.to_lowercase().as_str()) {
+                continue;
+            }
+            if let Ok(number) = value.parse::<u32>() {
+                // key the link by its other parameters with the page number blanked
+                let mut template = link.clone();
+                let pairs: Vec<(String, String)> = link
+                    .query_pairs()
+                    .map(|(k, v)| if k == name { (k.to_string(), String::new()) } else { (k.to_string(), v.to_string()) })
+                    .collect();
+                template.query_pairs_mut().clear().extend_pairs(pairs);
+                let last = last_pages.entry((template, name.to_string())).or_insert(0);
+                *last = (*last).max(number.min(MAX_PAGINATION_PAGES));
+            }
+        }
+    }
+
+    let mut pages = HashSet::new();
+
+    for ((template, name), last) in last_pages {
+        for number in 1..=last {
+            let mut page = template.clone();
+            let pairs: Vec<(String, String)> = template
+                .query_pairs()
+                .map(|(k, v)| if k == name { (k.to_string(), number.to_string()) } else { (k.to_string(), v.to_string()) })
+                .collect();
+            page.query_pairs_mut().clear().extend_pairs(pairs);
+            pages.insert(page);
+        }
+    }
+
+    pages
+}
+
+/// Get the raw targets of every `url(...)` in a css text.
+fn css_url_references(css: &str) -> Vec<&str> {
+    let mut references = Vec::new();
+    let mut rest = css;
+
+    while let Some(start) = rest.find("url(") {
+        rest = &rest[start + 4..];
+
+        match rest.find(')') {
+            Some(end) => {
+                let reference = rest[..end].trim().trim_matches(|c| c == '"' || c == '\'');
+                if !reference.is_empty() {
+                    references.push(reference);
+                }
+                rest = &rest[end..];
+            }
+            None => break,
+        }
+    }
+
+    references
+}
+
 #[test]
 fn parse_links() {
     let client = Client::builder()
@@ -147,26 +679,306 @@ fn test_abs_path() {
 
     assert_eq!(
         page.abs_path("/page"),
-        Url::parse("https://choosealicense.com/page").unwrap()
+        Some(Url::parse("https://choosealicense.com/page").unwrap())
     );
     assert_eq!(
         page.abs_path("/page?query=keyword"),
-        Url::parse("https://choosealicense.com/page?query=keyword").unwrap()
+        Some(Url::parse("https://choosealicense.com/page?query=keyword").unwrap())
     );
     assert_eq!(
         page.abs_path("/page#hash"),
-        Url::parse("https://choosealicense.com/page").unwrap()
+        Some(Url::parse("https://choosealicense.com/page").unwrap())
     );
     assert_eq!(
         page.abs_path("/page?query=keyword#hash"),
-        Url::parse("https://choosealicense.com/page?query=keyword").unwrap()
+        Some(Url::parse("https://choosealicense.com/page?query=keyword").unwrap())
+    );
+    assert_eq!(
+        page.abs_path("/page?"),
+        Some(Url::parse("https://choosealicense.com/page").unwrap())
     );
     assert_eq!(
         page.abs_path("#hash"),
-        Url::parse("https://choosealicense.com/").unwrap()
+        Some(Url::parse("https://choosealicense.com/").unwrap())
     );
     assert_eq!(
         page.abs_path("tel://+212 3456"),
-        Url::parse("https://choosealicense.com/").unwrap()
+        None
+    );
+    assert_eq!(page.abs_path("wss://choosealicense.com/socket"), None);
+}
+
+#[test]
+fn test_css_urls() {
+    let page = Page::build(
+        &Url::parse("https://choosealicense.com/").unwrap(),
+        r#"<html><head><style>body { background: url(/bg.png); }</style></head>
+        <body><div style="background-image: url('img/hero.jpg')"></div></body></html>"#,
+    );
+    let urls = page.css_urls();
+
+    assert!(urls.contains(&Url::parse("https://choosealicense.com/bg.png").unwrap()), "{:?}", urls);
+    assert!(urls.contains(&Url::parse("https://choosealicense.com/img/hero.jpg").unwrap()), "{:?}", urls);
+}
+
+#[test]
+fn test_language() {
+    let url = Url::parse("https://choosealicense.com/").unwrap();
+    let page = Page::build(&url, r#"<html lang="de"><body>Hallo</body></html>"#);
+    assert_eq!(page.language(), Some("de".to_string()));
+
+    let page = Page::build(&url, "<html><body>Hello</body></html>");
+    assert_eq!(page.language(), None);
+
+    // without a lang attribute the Content-Language header is used
+    use crate::utils::{mock_response, mock_server};
+    let address = mock_server(|request| {
+        let html = if request.starts_with("GET /lang ") { r#"<html lang="fr"></html>"# } else { "<html></html>" };
+        mock_response("200 OK", "Content-Type: text/html\r\nContent-Language: de-DE, en\r\n", html)
+    });
+    let client = Client::new();
+    let page = Page::new(&Url::parse(&address).unwrap(), &client);
+    assert_eq!(page.language(), Some("de-DE".to_string()));
+    let page = Page::new(&Url::parse(&format!("{}/lang", address)).unwrap(), &client);
+    assert_eq!(page.language(), Some("fr".to_string()));
+}
+
+#[test]
+fn test_status_code() {
+    use crate::utils::{mock_response, mock_server};
+
+    let address = mock_server(|request| {
+        if request.starts_with("GET /missing ") {
+            mock_response("404 Not Found", "Content-Type: text/html\r\n", "<p>not found</p>")
+        } else {
+            mock_response("200 OK", "Content-Type: text/html; charset=utf-8\r\n", "<p>found</p>")
+        }
+    });
+    let client = Client::new();
+
+    let page = Page::new(&Url::parse(&format!("{}/missing", address)).unwrap(), &client);
+    assert_eq!(page.get_status_code(), Some(StatusCode::NOT_FOUND));
+    assert_eq!(page.get_html(), "");
+
+    let page = Page::new(&Url::parse(&address).unwrap(), &client);
+    assert_eq!(page.get_status_code(), Some(StatusCode::OK));
+    assert_eq!(page.content_type(), Some("text/html".to_string()));
+    assert_eq!(page.get_headers()[CONTENT_TYPE], "text/html; charset=utf-8");
+    assert_eq!(Page::build(&Url::parse(&address).unwrap(), "").get_status_code(), Some(StatusCode::OK));
+
+    // nothing listens on the port of a dropped listener
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let unreachable = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    drop(listener);
+    let page = Page::new(&unreachable, &client);
+    assert!(page.get_error().is_some());
+    assert_eq!(page.get_status_code(), None);
+}
+
+#[test]
+fn test_noscript_links() {
+    let url = Url::parse("https://choosealicense.com/").unwrap();
+    let page = Page::build(
+        &url,
+        r#"<body><div id="app"></div><noscript><a href="/licenses/">licenses</a><a href="/about/">about</a></noscript></body>"#,
+    );
+    let expected: HashSet<Url> = vec![
+        Url::parse("https://choosealicense.com/licenses/").unwrap(),
+        Url::parse("https://choosealicense.com/about/").unwrap(),
+    ]
+    .into_iter()
+    .collect();
+
+    assert!(page.links().is_empty());
+    assert_eq!(page.noscript_links(), expected);
+    assert_eq!(page.links_with_configuration(&Configuration::new()), expected);
+}
+
+#[test]
+fn test_respect_nofollow() {
+    let page = Page::build(
+        &Url::parse("https://choosealicense.com/").unwrap(),
+        r#"<a href="/ads" rel="sponsored NoFollow">ads</a><a href="/about">about</a>"#,
+    );
+    let mut configuration = Configuration::new();
+    configuration.respect_nofollow = true;
+
+    assert_eq!(
+        page.links_with_configuration(&configuration),
+        vec![Url::parse("https://choosealicense.com/about").unwrap()].into_iter().collect()
+    );
+    assert_eq!(page.links().len(), 2);
+}
+
+#[test]
+fn test_meta_robots() {
+    let page = |robots: &str| {
+        Page::build(
+            &Url::parse("https://choosealicense.com/").unwrap(),
+            &format!(r#"<html><head><meta name="Robots" content="{}"></head><body><a href="/about">about</a></body></html>"#, robots),
+        )
+    };
+    let mut configuration = Configuration::new();
+    configuration.respect_meta_robots = true;
+
+    let noindex = page("noindex");
+    assert!(noindex.is_meta_noindex());
+    assert!(!noindex.is_meta_nofollow());
+    assert_eq!(noindex.links_with_configuration(&configuration).len(), 1);
+
+    let nofollow = page("index, NoFollow");
+    assert!(!nofollow.is_meta_noindex());
+    assert!(nofollow.links_with_configuration(&configuration).is_empty());
+    assert_eq!(nofollow.links_with_configuration(&Configuration::new()).len(), 1);
+
+    let none = page("none");
+    assert!(none.is_meta_noindex());
+    assert!(none.links_with_configuration(&configuration).is_empty());
+}
+
+#[test]
+fn test_onclick_links() {
+    let page = Page::build(
+        &Url::parse("https://choosealicense.com/").unwrap(),
+        r#"<div onclick="location.href='/licenses/'">licenses</div><button onclick="window.open(&quot;about.html&quot;, '_blank')">about</button><span onclick="alert('hi there')">hi</span>"#,
+    );
+    let mut configuration = Configuration::new();
+
+    assert!(page.links_with_configuration(&configuration).is_empty());
+
+    configuration.scan_onclick = true;
+    assert_eq!(
+        page.links_with_configuration(&configuration),
+        vec![
+            Url::parse("https://choosealicense.com/licenses/").unwrap(),
+            Url::parse("https://choosealicense.com/about.html").unwrap(),
+        ]
+        .into_iter()
+        .collect()
+    );
+}
+
+#[test]
+fn test_unfetchable_links() {
+    let url = Url::parse("https://choosealicense.com/").unwrap();
+    let page = Page::build(
+        &url,
+        r#"<a href="/next">next</a><a href="wss://choosealicense.com/socket.html">socket</a><div data-next="ws://choosealicense.com/live" onclick="location.href='wss://choosealicense.com/feed/'"></div><div data-next="tel://+212 3456"></div>"#,
+    );
+    let mut configuration = Configuration::new();
+    configuration.pagination_attrs = vec!["data-next".to_string()];
+    configuration.scan_onclick = true;
+
+    assert_eq!(
+        page.links_with_configuration(&configuration),
+        vec![Url::parse("https://choosealicense.com/next").unwrap()].into_iter().collect()
+    );
+}
+
+#[test]
+fn test_mixed_content() {
+    let html = r#"<a href="http://choosealicense.com/about">about</a><a href="/licenses/">licenses</a>
+        <img src="http://cdn.example.com/logo.png"><script src="https://cdn.example.com/app.js"></script>"#;
+    let page = Page::build(&Url::parse("https://choosealicense.com/").unwrap(), html);
+
+    assert_eq!(
+        page.mixed_content(),
+        vec!["http://choosealicense.com/about", "http://cdn.example.com/logo.png"]
+            .into_iter()
+            .map(|url| Url::parse(url).unwrap())
+            .collect()
+    );
+    assert!(Page::build(&Url::parse("http://choosealicense.com/").unwrap(), html).mixed_content().is_empty());
+}
+
+#[test]
+fn test_base_href() {
+    let url = Url::parse("https://choosealicense.com/licenses/mit/").unwrap();
+    let page = Page::build(
+        &url,
+        r#"<html><head><base href="/docs/"></head><body><a href="guide.html">guide</a><a data-next="page-2">next</a></body></html>"#,
+    );
+
+    assert_eq!(
+        page.all_hrefs(),
+        vec![("guide.html".to_string(), Some(Url::parse("https://choosealicense.com/docs/guide.html").unwrap()))]
+    );
+    assert_eq!(
+        page.attribute_links(&["data-next".to_string()]),
+        vec![Url::parse("https://choosealicense.com/docs/page-2").unwrap()].into_iter().collect()
+    );
+
+    let page = Page::build(&url, r#"<base href="javascript:void(0)"><a href="guide.html">guide</a>"#);
+    assert_eq!(page.all_hrefs()[0].1, Some(Url::parse("https://choosealicense.com/licenses/mit/guide.html").unwrap()));
+}
+
+#[test]
+fn test_link_element_links() {
+    let page = Page::build(
+        &Url::parse("https://choosealicense.com/").unwrap(),
+        r#"<html><head><link rel="next" href="/page/2"><link rel="prev" href="/page/0"><link rel="stylesheet" href="/main.css"></head>
+        <body><map><area href="/map-area" shape="rect"></map><iframe src="/frame"></iframe></body></html>"#,
+    );
+    let mut configuration = Configuration::new();
+
+    assert!(page.links_with_configuration(&configuration).is_empty());
+
+    configuration.include_link_elements = true;
+    assert_eq!(
+        page.links_with_configuration(&configuration),
+        vec!["/page/2", "/page/0", "/map-area", "/frame"]
+            .into_iter()
+            .map(|path| Url::parse("https://choosealicense.com/").unwrap().join(path).unwrap())
+            .collect()
+    );
+}
+
+#[test]
+fn test_json_ld() {
+    let page = Page::build(
+        &Url::parse("https://choosealicense.com/").unwrap(),
+        r#"<head>
+            <script type="application/ld+json">{"@context": "https://schema.org", "@type": "Organization", "name": "Spider"}</script>
+            <script type="application/ld+json">{"@type": "Person", "name": </script>
+        </head>"#,
+    );
+
+    assert_eq!(
+        page.json_ld(),
+        vec![serde_json::json!({"@context": "https://schema.org", "@type": "Organization", "name": "Spider"})]
+    );
+}
+
+#[test]
+fn test_download_links() {
+    let page = Page::build(
+        &Url::parse("https://choosealicense.com/").unwrap(),
+        r#"<a href="/files/report" download="report.pdf">report</a><a href="/data.csv" download>data</a><a href="/about/">about</a>"#,
+    );
+
+    assert_eq!(
+        page.download_links(),
+        vec![
+            (Url::parse("https://choosealicense.com/files/report").unwrap(), Some("report.pdf".to_string())),
+            (Url::parse("https://choosealicense.com/data.csv").unwrap(), None),
+        ]
+    );
+}
+
+#[test]
+fn test_all_hrefs() {
+    let page = Page::build(
+        &Url::parse("https://choosealicense.com/").unwrap(),
+        r#"<a href="/about/">about</a><a href="https://example.org/x#top">external</a><a href="logo.png">media</a><a href="http://[broken">broken</a>"#,
+    );
+
+    assert_eq!(
+        page.all_hrefs(),
+        vec![
+            ("/about/".to_string(), Some(Url::parse("https://choosealicense.com/about/").unwrap())),
+            ("https://example.org/x#top".to_string(), Some(Url::parse("https://example.org/x").unwrap())),
+            ("logo.png".to_string(), Some(Url::parse("https://choosealicense.com/logo.png").unwrap())),
+            ("http://[broken".to_string(), None),
+        ]
     );
 }
@@ -0,0 +1,184 @@
+use crate::utils::log;
+use flate2::read::GzDecoder;
+use hashbrown::HashSet;
+use reqwest::blocking::Client;
+use std::io::Read;
+use url::Url;
+
+/// Deepest level of nested sitemap index files followed.
+const MAX_SITEMAP_DEPTH: usize = 4;
+
+/// Content of a sitemap file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sitemap {
+    /// `<sitemapindex>` listing child sitemaps.
+    Index(Vec<Url>),
+    /// `<urlset>` listing pages.
+    UrlSet(Vec<Url>),
+}
+
+/// Parse the `<loc>` entries of a sitemap or sitemap index, invalid URL are skipped.
+pub fn parse_sitemap(xml: &str) -> Sitemap {
+    let mut locs = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find("<loc>") {
+        rest = &rest[start + "<loc>".len()..];
+        let end = match rest.find("</loc>") {
+            Some(end) => end,
+            None => break,
+        };
+        let loc = unescape(rest[..end].trim().trim_start_matches("<![CDATA[").trim_end_matches("]]>"));
+
+        if let Ok(url) = Url::parse(loc.trim()) {
+            locs.push(url);
+        }
+        rest = &rest[end..];
+    }
+
+    if xml.contains("<sitemapindex") {
+        Sitemap::Index(locs)
+    } else {
+        Sitemap::UrlSet(locs)
+    }
+}
+
+/// Text of a sitemap body, gzip compressed `.xml.gz` bodies are decompressed.
+pub fn decode_sitemap(body: &[u8]) -> String {
+    if body.starts_with(&[0x1f, 0x8b]) {
+        let mut xml = String::new();
+        if GzDecoder::new(body).read_to_string(&mut xml).is_ok() {
+            return xml;
+        }
+    }
+
+    String::from_utf8_lossy(body).to_string()
+}
+
+/// URL of the `Sitemap:` directives of a robots.txt body.
+pub fn robots_sitemaps(robots: &str) -> Vec<Url> {
+    robots
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            if name.trim().eq_ignore_ascii_case("sitemap") {
+                Url::parse(value.trim()).ok()
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Fetch a sitemap and the child sitemaps of sitemap indexes, returning the page URL listed.
+pub fn fetch_sitemap_urls(url: &Url, client: &Client) -> Vec<Url> {
+    let mut urls = Vec::new();
+    let mut seen = HashSet::new();
+
+    fetch_sitemap(url, client, 0, &mut seen, &mut urls);
+
+    urls
+}
+
+/// Fetch a sitemap recursively up to `MAX_SITEMAP_DEPTH` levels.
+fn fetch_sitemap(url: &Url, client: &Client, depth: usize, seen: &mut HashSet<Url>, urls: &mut Vec<Url>) {
+    if depth > MAX_SITEMAP_DEPTH || !seen.insert(url.clone()) {
+        return;
+    }
+    let body = match client.get(url.as_str()).send().and_then(|res| res.error_for_status()).and_then(|res| res.bytes()) {
+        Ok(body) => body,
+        Err(_) => {
+            log("- error fetching sitemap {}", url);
+            return;
+        }
+    };
+
+    match parse_sitemap(&decode_sitemap(&body)) {
+        Sitemap::Index(sitemaps) => {
+            for sitemap in sitemaps {
+                fetch_sitemap(&sitemap, client, depth + 1, seen, urls);
+            }
+        }
+        Sitemap::UrlSet(locs) => urls.extend(locs),
+    }
+}
+
+/// Fetch a sitemap and the child sitemaps of sitemap indexes without blocking the runtime,
+/// returning the page URL listed in the same order as `fetch_sitemap_urls`.
+pub async fn fetch_sitemap_urls_async(url: &Url, client: &reqwest::Client) -> Vec<Url> {
+    let mut urls = Vec::new();
+    let mut seen = HashSet::new();
+    let mut pending = vec![(url.clone(), 0)];
+
+    while let Some((url, depth)) = pending.pop() {
+        if depth > MAX_SITEMAP_DEPTH || !seen.insert(url.clone()) {
+            continue;
+        }
+        let body = match client.get(url.as_str()).send().await.and_then(|res| res.error_for_status()) {
+            Ok(res) => res.bytes().await,
+            Err(error) => Err(error),
+        };
+        let body = match body {
+            Ok(body) => body,
+            Err(_) => {
+                log("- error fetching sitemap {}", &url);
+                continue;
+            }
+        };
+
+        match parse_sitemap(&decode_sitemap(&body)) {
+            Sitemap::Index(sitemaps) => pending.extend(sitemaps.into_iter().rev().map(|sitemap| (sitemap, depth + 1))),
+            Sitemap::UrlSet(locs) => urls.extend(locs),
+        }
+    }
+
+    urls
+}
+
+/// Replace the predefined XML entities.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[test]
+fn test_parse_sitemap() {
+    let index = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>https://example.com/sitemap-1.xml</loc></sitemap>
+</sitemapindex>"#;
+    let urlset = r#"<urlset><url><loc> https://example.com/a?x=1&amp;y=2 </loc></url><url><loc>not a url</loc></url></urlset>"#;
+
+    assert_eq!(parse_sitemap(index), Sitemap::Index(vec![Url::parse("https://example.com/sitemap-1.xml").unwrap()]));
+    assert_eq!(parse_sitemap(urlset), Sitemap::UrlSet(vec![Url::parse("https://example.com/a?x=1&y=2").unwrap()]));
+}
+
+#[test]
+fn test_decode_sitemap() {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let xml = "<urlset><url><loc>https://example.com/</loc></url></urlset>";
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(xml.as_bytes()).unwrap();
+
+    assert_eq!(decode_sitemap(&encoder.finish().unwrap()), xml);
+    assert_eq!(decode_sitemap(xml.as_bytes()), xml);
+}
+
+#[test]
+fn test_robots_sitemaps() {
+    let robots = "User-agent: *\nDisallow: /admin\nSitemap: https://example.com/sitemap.xml\nsitemap:https://example.com/news.xml\n";
+
+    assert_eq!(
+        robots_sitemaps(robots),
+        vec![
+            Url::parse("https://example.com/sitemap.xml").unwrap(),
+            Url::parse("https://example.com/news.xml").unwrap(),
+        ]
+    );
+}
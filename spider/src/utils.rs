@@ -1,26 +1,998 @@
+use crate::configuration::Configuration;
 use reqwest::blocking::{Client};
 use reqwest::StatusCode;
+use reqwest::header::{
+    HeaderMap, HeaderValue, CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+    RANGE, RETRY_AFTER, VARY,
+};
 use log::{log_enabled, info, Level};
+use hashbrown::HashMap;
+use publicsuffix::{List, Psl};
 use url::Url;
+use std::error::Error;
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryFrom;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// TLS details of a crawled host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsInfo {
+    /// DER encoded leaf certificate of the host.
+    pub peer_certificate: Vec<u8>,
+    /// expiry of the leaf certificate, `None` when the certificate cannot be parsed.
+    pub not_after: Option<SystemTime>,
+    /// negotiated protocol version, e.g. `TLSv1.3`.
+    pub protocol: Option<String>,
+    /// negotiated cipher suite, e.g. `TLS13_AES_128_GCM_SHA256`.
+    pub cipher: Option<String>,
+}
+
+impl TlsInfo {
+    /// TLS details of a DER encoded leaf certificate, the protocol and the cipher are set by `tls_handshake`.
+    pub fn new(peer_certificate: &[u8]) -> Self {
+        let not_after = x509_parser::parse_x509_certificate(peer_certificate)
+            .ok()
+            .and_then(|(_, certificate)| u64::try_from(certificate.validity().not_after.timestamp()).ok())
+            .map(|seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+
+        Self {
+            peer_certificate: peer_certificate.to_vec(),
+            not_after,
+            protocol: None,
+            cipher: None,
+        }
+    }
+}
+
+/// Certificate verifier accepting any certificate, `tls_handshake` only reads the negotiated
+/// parameters and the requests verify the certificates.
+struct AnyCertificate;
+
+impl rustls::client::ServerCertVerifier for AnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+/// Negotiated protocol version and cipher suite of a TLS handshake with the host of an https URL.
+pub fn tls_handshake(url: &Url, timeout: Duration) -> Option<(String, String)> {
+    use std::net::{TcpStream, ToSocketAddrs};
+
+    let host = url.host_str()?;
+    let address = (host, url.port_or_known_default()?).to_socket_addrs().ok()?.next()?;
+    let mut stream = TcpStream::connect_timeout(&address, timeout).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    stream.set_write_timeout(Some(timeout)).ok()?;
+
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(AnyCertificate))
+        .with_no_client_auth();
+    let server_name = rustls::ServerName::try_from(host.trim_start_matches('[').trim_end_matches(']')).ok()?;
+    let mut connection = rustls::ClientConnection::new(Arc::new(config), server_name).ok()?;
+    while connection.is_handshaking() {
+        connection.complete_io(&mut stream).ok()?;
+    }
+    let protocol = match connection.protocol_version()? {
+        rustls::ProtocolVersion::TLSv1_2 => "TLSv1.2".to_string(),
+        rustls::ProtocolVersion::TLSv1_3 => "TLSv1.3".to_string(),
+        version => format!("{:?}", version),
+    };
+    let cipher = format!("{:?}", connection.negotiated_cipher_suite()?.suite());
+
+    Some((protocol, cipher))
+}
+
+/// Response of a page request.
+#[derive(Debug, Clone, Default)]
+pub struct PageResponse {
+    /// status code of the response.
+    pub status: StatusCode,
+    /// body text of a successful response.
+    pub body: String,
+    /// TLS details when the client captures them.
+    pub tls_info: Option<TlsInfo>,
+    /// the response is a download served with `Content-Disposition: attachment`.
+    pub attachment: bool,
+    /// headers of the response.
+    pub headers: HeaderMap,
+    /// hyperlinks of a PDF document response when parsing PDFs.
+    pub pdf_links: Vec<String>,
+    /// URL the request was redirected to.
+    pub redirect_url: Option<Url>,
+}
 
 /// Perform a network request to a resource extracting all content as text.
-pub fn fetch_page_html(url: &Url, client: &Client) -> String {
-    let mut body = String::new();
+/// Transport failures are returned as a short error description.
+pub fn fetch_page_html(url: &Url, client: &Client) -> Result<String, String> {
+    fetch(url, client, &FetchOptions::html(), HeaderMap::new()).map(|res| res.body)
+}
+
+/// Perform a network request to a resource with the retries allowed by the configuration.
+/// Transport errors, 5xx and 429 responses are retried with an exponential backoff, the last
+/// outcome is returned once the retries are exhausted: the error or the failing status.
+pub fn fetch_page(url: &Url, client: &Client, configuration: &Configuration) -> Result<PageResponse, String> {
+    if is_head_prechecked(url, configuration) {
+        if let Some(res) = head_precheck(url, client, configuration.page_timeout) {
+            return Ok(res);
+        }
+    }
+
+    let options = FetchOptions::new(configuration);
+    let (headers, cached) = cached_request(url, configuration);
+    let mut result = fetch(url, client, &options, headers.clone());
+    let mut retries = 0;
+
+    while let Some(delay) = next_retry(url, &result, configuration, retries) {
+        std::thread::sleep(delay);
+        retries += 1;
+        result = fetch(url, client, &options, headers.clone());
+    }
+
+    finish_fetch(url, configuration, result, cached)
+}
+
+/// Perform an async network request to a resource extracting all content as text.
+/// Transport failures are returned as a short error description.
+pub async fn fetch_page_html_async(url: &Url, client: &reqwest::Client) -> Result<String, String> {
+    fetch_async(url, client, &FetchOptions::html(), HeaderMap::new()).await.map(|res| res.body)
+}
+
+/// Perform an async network request to a resource with the retries allowed by the configuration,
+/// like `fetch_page`.
+pub async fn fetch_page_async(
+    url: &Url,
+    client: &reqwest::Client,
+    configuration: &Configuration,
+) -> Result<PageResponse, String> {
+    if is_head_prechecked(url, configuration) {
+        if let Some(res) = head_precheck_async(url, client, configuration.page_timeout).await {
+            return Ok(res);
+        }
+    }
+
+    let options = FetchOptions::new(configuration);
+    let (headers, cached) = cached_request(url, configuration);
+    let mut result = fetch_async(url, client, &options, headers.clone()).await;
+    let mut retries = 0;
+
+    while let Some(delay) = next_retry(url, &result, configuration, retries) {
+        tokio::time::sleep(delay).await;
+        retries += 1;
+        result = fetch_async(url, client, &options, headers.clone()).await;
+    }
+
+    finish_fetch(url, configuration, result, cached)
+}
+
+/// return `true` if the content type of the URL is checked with a HEAD request before fetching it.
+fn is_head_prechecked(url: &Url, configuration: &Configuration) -> bool {
+    configuration.head_precheck && url.scheme() != "file"
+}
+
+/// Headers of a page request, the configured headers with the validators of the cached response
+/// of the URL, and the cached response.
+fn cached_request(url: &Url, configuration: &Configuration) -> (HeaderMap, Option<(HeaderMap, String)>) {
+    let cached = configuration.cache_dir.as_ref().and_then(|dir| read_cache(dir, url, &configuration.headers));
+    let mut headers = configuration.headers.clone();
+    headers.extend(cached.as_ref().map(|(validators, _)| validators.clone()).unwrap_or_default());
+
+    (headers, cached)
+}
+
+/// Delay before retrying the request outcome, `None` when it is not retried or the retries
+/// allowed by the configuration are exhausted.
+fn next_retry(
+    url: &Url,
+    result: &Result<PageResponse, String>,
+    configuration: &Configuration,
+    retries: usize,
+) -> Option<Duration> {
+    if retries >= configuration.max_retries || !should_retry(result, configuration) {
+        return None;
+    }
+    log("- retry {}", url);
+
+    Some(retry_delay(result, configuration, retries))
+}
+
+/// return `true` if the request outcome is retried: transport errors, 5xx and 429 responses and
+/// empty bodies when `retry_empty_body` is set.
+fn should_retry(result: &Result<PageResponse, String>, configuration: &Configuration) -> bool {
+    match result {
+        Ok(res) if res.status == StatusCode::OK => {
+            configuration.retry_empty_body && res.body.is_empty() && !res.attachment
+        }
+        Ok(res) => res.status.is_server_error() || res.status == StatusCode::TOO_MANY_REQUESTS,
+        Err(_) => true,
+    }
+}
+
+/// Delay before the given retry, the `Retry-After` of 429 and 503 responses or the backoff.
+fn retry_delay(result: &Result<PageResponse, String>, configuration: &Configuration, retry: usize) -> Duration {
+    retry_after(result).unwrap_or_else(|| retry_backoff(configuration.retry_backoff, retry, configuration.retry_jitter))
+}
+
+/// Delay requested by the `Retry-After` header of a 429 or 503 response, in seconds or as an HTTP date.
+fn retry_after(result: &Result<PageResponse, String>) -> Option<Duration> {
+    let res = result.as_ref().ok()?;
+    if res.status != StatusCode::TOO_MANY_REQUESTS && res.status != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+    let value = res.headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()
+            .map(|date| date.duration_since(SystemTime::now()).unwrap_or_default()),
+    }
+}
+
+/// Apply the cache and the content type filter of the configuration to the final request outcome.
+fn finish_fetch(
+    url: &Url,
+    configuration: &Configuration,
+    mut result: Result<PageResponse, String>,
+    cached: Option<(HeaderMap, String)>,
+) -> Result<PageResponse, String> {
+    if let (Some(dir), Ok(res)) = (&configuration.cache_dir, &mut result) {
+        match cached {
+            Some((_, body)) if res.status == StatusCode::NOT_MODIFIED => {
+                res.status = StatusCode::OK;
+                res.body = body;
+            }
+            _ if res.status == StatusCode::OK => write_cache(dir, url, res, &configuration.headers),
+            _ => (),
+        }
+    }
+
+    if let (Some(allowed), Ok(res)) = (&configuration.allowed_content_types, &mut result) {
+        if url.scheme() != "file" && !is_allowed_content_type(&res.headers, allowed) {
+            log("- skip content type {}", url);
+            res.body.clear();
+        }
+    }
+
+    result
+}
+
+/// return `true` if the `Content-Type` of the headers is one of the allowed MIME types.
+fn is_allowed_content_type(headers: &HeaderMap, allowed: &[String]) -> bool {
+    match headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok()) {
+        Some(content_type) => {
+            let essence = mime_essence(content_type);
+            allowed.iter().any(|a| a.eq_ignore_ascii_case(&essence))
+        }
+        None => false,
+    }
+}
+
+/// Request the headers of a resource and return them when its content type is not html,
+/// `None` when the resource has to be fetched.
+fn head_precheck(url: &Url, client: &Client, timeout: Option<Duration>) -> Option<PageResponse> {
+    let (status, headers) = head(url, client, timeout).ok()?;
+
+    non_html_response(url, status, &headers)
+}
+
+/// Async `head_precheck`.
+async fn head_precheck_async(url: &Url, client: &reqwest::Client, timeout: Option<Duration>) -> Option<PageResponse> {
+    let (status, headers) = head_async(url, client, timeout).await.ok()?;
+
+    non_html_response(url, status, &headers)
+}
+
+/// Request the status of a resource with HEAD, falling back to a ranged GET when HEAD is not supported.
+pub fn fetch_status(url: &Url, client: &Client) -> Result<StatusCode, String> {
+    head(url, client, None)
+        .map(|(status, _)| status)
+        .map_err(|e| error_description(&e))
+}
+
+/// Request the status and headers of a resource with HEAD. Servers answering HEAD with
+/// 405 or 501 are asked for the first byte with a ranged GET instead, its partial content
+/// status is reported as `200 OK`.
+fn head(url: &Url, client: &Client, timeout: Option<Duration>) -> Result<(StatusCode, HeaderMap), reqwest::Error> {
+    let send = |mut request: reqwest::blocking::RequestBuilder| {
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        request.send()
+    };
+    let mut res = send(client.head(url.to_string()))?;
+
+    if is_head_unsupported(res.status()) {
+        res = send(client.get(url.to_string()).header(RANGE, "bytes=0-0"))?;
+    }
+
+    Ok((ranged_status(res.status()), res.headers().clone()))
+}
+
+/// Async `head`.
+async fn head_async(url: &Url, client: &reqwest::Client, timeout: Option<Duration>) -> Result<(StatusCode, HeaderMap), reqwest::Error> {
+    let send = |mut request: reqwest::RequestBuilder| {
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        request.send()
+    };
+    let mut res = send(client.head(url.to_string())).await?;
+
+    if is_head_unsupported(res.status()) {
+        res = send(client.get(url.to_string()).header(RANGE, "bytes=0-0")).await?;
+    }
+
+    Ok((ranged_status(res.status()), res.headers().clone()))
+}
+
+/// return `true` if the server does not support HEAD requests.
+fn is_head_unsupported(status: StatusCode) -> bool {
+    status == StatusCode::METHOD_NOT_ALLOWED || status == StatusCode::NOT_IMPLEMENTED
+}
+
+/// The status of a ranged request as if the whole resource was requested.
+fn ranged_status(status: StatusCode) -> StatusCode {
+    if status == StatusCode::PARTIAL_CONTENT {
+        StatusCode::OK
+    } else {
+        status
+    }
+}
+
+/// The response of a HEAD request when its content type is not html.
+fn non_html_response(url: &Url, status: StatusCode, headers: &HeaderMap) -> Option<PageResponse> {
+    let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+
+    if is_html_content_type(content_type) {
+        None
+    } else {
+        log("- skip non html {}", url);
+        Some(PageResponse {
+            status,
+            headers: headers.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+/// return `true` if the `Content-Type` header value is an html document.
+fn is_html_content_type(content_type: &str) -> bool {
+    let essence = mime_essence(content_type);
+
+    essence == "text/html" || essence == "application/xhtml+xml"
+}
+
+/// MIME type of a `Content-Type` header value without its parameters, lowercased.
+pub fn mime_essence(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+/// Path of the cache entry of a URL without extension, `variant` holding the request header
+/// values the response varies on.
+fn cache_path(dir: &Path, url: &Url, variant: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    url.as_str().hash(&mut hasher);
+    if !variant.is_empty() {
+        variant.hash(&mut hasher);
+    }
+
+    dir.join(format!("{:016x}", hasher.finish()))
+}
+
+/// Lowercase header names of the `Vary` header of a response, `None` for `Vary: *` responses
+/// varying on more than the request headers.
+fn vary_names(headers: &HeaderMap) -> Option<Vec<String>> {
+    let mut names: Vec<String> = headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+    names.sort();
+    names.dedup();
+
+    if names.iter().any(|name| name == "*") {
+        None
+    } else {
+        Some(names)
+    }
+}
+
+/// The values of the request headers a response varies on, as `name: value` lines.
+fn cache_variant(names: &[String], request_headers: &HeaderMap) -> String {
+    names
+        .iter()
+        .map(|name| {
+            let value = request_headers.get(name.as_str()).and_then(|value| value.to_str().ok()).unwrap_or_default();
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Read the conditional request headers and the body cached for a URL and the request headers
+/// its response varies on.
+fn read_cache(dir: &Path, url: &Url, request_headers: &HeaderMap) -> Option<(HeaderMap, String)> {
+    let names: Vec<String> = fs::read_to_string(cache_path(dir, url, "").with_extension("vary"))
+        .map(|vary| vary.lines().map(|name| name.to_string()).collect())
+        .unwrap_or_default();
+    let path = cache_path(dir, url, &cache_variant(&names, request_headers));
+    let meta = fs::read_to_string(path.with_extension("meta")).ok()?;
+    let body = fs::read_to_string(path.with_extension("body")).ok()?;
+    let mut validators = HeaderMap::new();
+
+    for line in meta.lines() {
+        if let Some((name, value)) = line.split_once(": ") {
+            let header = match name {
+                "etag" => IF_NONE_MATCH,
+                "last-modified" => IF_MODIFIED_SINCE,
+                _ => continue,
+            };
+            if let Ok(value) = HeaderValue::from_str(value) {
+                validators.insert(header, value);
+            }
+        }
+    }
+
+    if validators.is_empty() {
+        None
+    } else {
+        Some((validators, body))
+    }
+}
+
+/// Cache the body of a response with its validators, responses without validators are not cached.
+/// Responses with a `Vary` header are cached per value of the request headers named.
+fn write_cache(dir: &Path, url: &Url, res: &PageResponse, request_headers: &HeaderMap) {
+    let meta: Vec<String> = [ETAG, LAST_MODIFIED]
+        .iter()
+        .filter_map(|name| {
+            res.headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| format!("{}: {}", name, value))
+        })
+        .collect();
+    let names = match vary_names(&res.headers) {
+        Some(names) if !meta.is_empty() => names,
+        _ => return,
+    };
+    let path = cache_path(dir, url, &cache_variant(&names, request_headers));
+    let written = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(cache_path(dir, url, "").with_extension("vary"), names.join("\n")))
+        .and_then(|_| fs::write(path.with_extension("body"), &res.body))
+        .and_then(|_| fs::write(path.with_extension("meta"), meta.join("\n")));
+
+    if written.is_err() {
+        log("- error writing cache {}", url);
+    }
+}
+
+/// Delay before the given retry, the base doubled on each retry with a random jitter up to the
+/// `jitter` share of the base.
+pub fn retry_backoff(base: Duration, retry: usize, jitter: f64) -> Duration {
+    if base.is_zero() {
+        return base;
+    }
+    let exponential = base.saturating_mul(1 << retry.min(16) as u32);
+
+    exponential.saturating_add(base.mul_f64(jitter.max(0.0) * rand::random::<f64>()))
+}
+
+/// Options of a single page request.
+#[derive(Debug, Default, Clone, Copy)]
+struct FetchOptions {
+    /// timeout of the request regardless of the client timeout.
+    timeout: Option<Duration>,
+    /// read the body of attachment downloads.
+    attachments: bool,
+    /// extract the links of PDF documents.
+    pdfs: bool,
+}
+
+impl FetchOptions {
+    /// Options of the page requests of a crawl.
+    fn new(configuration: &Configuration) -> Self {
+        Self {
+            timeout: configuration.page_timeout,
+            attachments: configuration.parse_attachments,
+            pdfs: configuration.parse_pdfs,
+        }
+    }
+
+    /// Options reading any body as text.
+    fn html() -> Self {
+        Self {
+            attachments: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Part of a response body read.
+enum BodyRead {
+    /// the links of the PDF document.
+    Pdf,
+    /// the whole body as text.
+    Text,
+    /// nothing, the body is not needed.
+    Skip,
+}
+
+/// Part of the body read for a response: the body of successful responses, PDF documents
+/// only for their links and attachment downloads only when `attachments` is set.
+fn body_read(response: &PageResponse, options: &FetchOptions) -> BodyRead {
+    if response.status != StatusCode::OK {
+        BodyRead::Skip
+    } else if options.pdfs && is_pdf(&response.headers) {
+        BodyRead::Pdf
+    } else if options.attachments || !response.attachment {
+        BodyRead::Text
+    } else {
+        BodyRead::Skip
+    }
+}
+
+/// Log a failed request and describe its error.
+fn fetch_error(message: &'static str, url: &Url, error: &reqwest::Error) -> String {
+    log(message, url);
+    error_description(error)
+}
+
+/// Perform a network request returning the status and the body of successful responses.
+/// The request fails once the timeout elapses regardless of the client timeout.
+fn fetch(url: &Url, client: &Client, options: &FetchOptions, headers: HeaderMap) -> Result<PageResponse, String> {
+    if url.scheme() == "file" {
+        return fetch_file(url);
+    }
+
+    let mut request = client.get(url.to_string()).headers(headers);
+
+    if let Some(timeout) = options.timeout {
+        request = request.timeout(timeout);
+    }
+
+    let res = request.send().map_err(|e| fetch_error("- error fetching {}", url, &e))?;
+    let mut response = response_head(res.status(), res.headers(), res.extensions().get());
+    response.redirect_url = Some(res.url().clone()).filter(|redirect_url| redirect_url != url);
+
+    match body_read(&response, options) {
+        BodyRead::Pdf => response.pdf_links = pdf_links(&res.bytes().map_err(|e| error_description(&e))?),
+        BodyRead::Text => response.body = res.text().map_err(|e| fetch_error("- error parsing html text {}", url, &e))?,
+        BodyRead::Skip => (),
+    }
+
+    Ok(response)
+}
+
+/// Async `fetch`.
+async fn fetch_async(
+    url: &Url,
+    client: &reqwest::Client,
+    options: &FetchOptions,
+    headers: HeaderMap,
+) -> Result<PageResponse, String> {
+    if url.scheme() == "file" {
+        return fetch_file(url);
+    }
+
+    let mut request = client.get(url.to_string()).headers(headers);
+
+    if let Some(timeout) = options.timeout {
+        request = request.timeout(timeout);
+    }
+
+    let res = request.send().await.map_err(|e| fetch_error("- error fetching {}", url, &e))?;
+    let mut response = response_head(res.status(), res.headers(), res.extensions().get());
+    response.redirect_url = Some(res.url().clone()).filter(|redirect_url| redirect_url != url);
+
+    match body_read(&response, options) {
+        BodyRead::Pdf => response.pdf_links = pdf_links(&res.bytes().await.map_err(|e| error_description(&e))?),
+        BodyRead::Text => response.body = res.text().await.map_err(|e| fetch_error("- error parsing html text {}", url, &e))?,
+        BodyRead::Skip => (),
+    }
+
+    Ok(response)
+}
+
+/// return `true` if the `Content-Type` of the headers is a PDF document.
+fn is_pdf(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| mime_essence(content_type) == "application/pdf")
+}
+
+/// Hyperlinks of the URI actions of a PDF document, like the link annotations.
+#[cfg(feature = "pdf")]
+pub fn pdf_links(pdf: &[u8]) -> Vec<String> {
+    use lopdf::{Dictionary, Document, Object};
+
+    /// the URI of an action dictionary or of the action of an annotation.
+    fn uri(dictionary: &Dictionary) -> Option<String> {
+        if let Ok(action) = dictionary.get(b"A").and_then(Object::as_dict) {
+            return uri(action);
+        }
+        if dictionary.get(b"S").and_then(Object::as_name).ok()? != b"URI" {
+            return None;
+        }
+        let uri = dictionary.get(b"URI").and_then(Object::as_str).ok()?;
+
+        Some(String::from_utf8_lossy(uri).trim().to_string())
+    }
+
+    match Document::load_mem(pdf) {
+        Ok(document) => {
+            let mut links: Vec<String> = document
+                .objects
+                .values()
+                .filter_map(|object| object.as_dict().ok().and_then(uri))
+                .collect();
+            links.dedup();
+            links
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Hyperlinks of a PDF document, PDFs are only parsed with the `pdf` feature.
+#[cfg(not(feature = "pdf"))]
+pub fn pdf_links(_pdf: &[u8]) -> Vec<String> {
+    Vec::new()
+}
 
-    match client.get(url.to_string()).send() {
-        Ok(res) if res.status() == StatusCode::OK => match res.text() {
-            Ok(text) => body = text,
-            Err(_) => {
-                log("- error fetching {}", &url);
-            },
-        },
-        Ok(_) => (),
-        Err(_) => {
-            log("- error parsing html text {}", &url);
+/// Response without body from the status, the headers and the TLS details of a request.
+fn response_head(status: StatusCode, headers: &HeaderMap, tls_info: Option<&reqwest::tls::TlsInfo>) -> PageResponse {
+    PageResponse {
+        status,
+        headers: headers.clone(),
+        tls_info: tls_info
+            .and_then(|info| info.peer_certificate())
+            .map(TlsInfo::new),
+        attachment: headers
+            .get(CONTENT_DISPOSITION)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.trim().to_ascii_lowercase().starts_with("attachment")),
+        ..Default::default()
+    }
+}
+
+/// Read a local file resource, directories serve their `index.html`.
+fn fetch_file(url: &Url) -> Result<PageResponse, String> {
+    let mut path = url.to_file_path().map_err(|_| format!("invalid file path {}", url))?;
+
+    if path.is_dir() {
+        path.push("index.html");
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(body) => Ok(PageResponse {
+            body,
+            ..Default::default()
+        }),
+        Err(e) => {
+            log("- error reading file {}", url);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Describe a request error by its root cause without the url so equal failures can be grouped.
+pub fn error_description(error: &reqwest::Error) -> String {
+    let mut source: &dyn Error = error;
+
+    while let Some(inner) = source.source() {
+        source = inner;
+    }
+
+    source.to_string()
+}
+
+lazy_static! {
+    /// Public suffix list snapshot from https://publicsuffix.org/list/public_suffix_list.dat
+    static ref PUBLIC_SUFFIX_LIST: List = include_str!("public_suffix_list.dat")
+        .parse()
+        .expect("Invalid public suffix list.");
+}
+
+/// 64 bits SimHash fingerprint of the words of a text, near-identical texts have fingerprints
+/// differing by a few bits.
+pub fn simhash(text: &str) -> u64 {
+    let mut weights = [0i64; 64];
+
+    for word in text.split_whitespace() {
+        let mut hasher = DefaultHasher::new();
+        word.to_lowercase().hash(&mut hasher);
+        let hash = hasher.finish();
+
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if (hash >> bit) & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |fingerprint, (bit, _)| fingerprint | (1 << bit))
+}
+
+/// Canonical form of a URL for dedup without an empty query. The URL parser already lowercases
+/// the host and drops the default port of http and https URL.
+pub fn canonicalize(url: &Url) -> Url {
+    let mut url = url.clone();
+
+    if url.query() == Some("") {
+        url.set_query(None);
+    }
+
+    url
+}
+
+/// The URL without the trailing slash of its http or https path, `/page/` and `/page` are the
+/// same resource. The root path is kept.
+pub fn canonical_trailing_slash(url: &Url) -> Url {
+    let mut url = url.clone();
+
+    if matches!(url.scheme(), "http" | "https") && url.path() != "/" {
+        if let Some(path) = url.path().strip_suffix('/').map(str::to_string) {
+            url.set_path(&path);
+        }
+    }
+
+    url
+}
+
+/// return `true` if the URL scheme can be fetched: `http`, `https` or `file`.
+pub fn is_fetchable(url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "https" | "file")
+}
+
+/// return `true` if the text matches the glob pattern, `*` matching any sequence of characters
+/// and `?` a single character.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` and of the text it matched up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, t));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Registrable domain of a host, the public suffix with one more label, e.g. `example.co.uk`
+/// for `blog.example.co.uk`.
+pub fn registrable_domain(host: &str) -> Option<String> {
+    let host = host.trim_end_matches('.').to_lowercase();
+
+    PUBLIC_SUFFIX_LIST
+        .domain(host.as_bytes())
+        .and_then(|domain| std::str::from_utf8(domain.as_bytes()).ok().map(|d| d.to_string()))
+}
+
+/// Soft limit of open file descriptors for the process.
+#[cfg(unix)]
+pub fn fd_limit() -> Option<u64> {
+    rlimit::getrlimit(rlimit::Resource::NOFILE).ok().map(|(soft, _)| soft)
+}
+
+/// Soft limit of open file descriptors for the process.
+#[cfg(not(unix))]
+pub fn fd_limit() -> Option<u64> {
+    None
+}
+
+/// Clamp concurrency to half of the file descriptor limit so connections can't exhaust it.
+pub fn clamp_concurrency(concurrency: usize, fd_limit: Option<u64>) -> usize {
+    match fd_limit {
+        Some(limit) => concurrency.min(((limit / 2) as usize).max(1)),
+        None => concurrency,
+    }
+}
+
+/// Counting semaphore blocking the thread, or the task with `acquire_async`, until a permit is available.
+#[derive(Debug)]
+pub struct Semaphore {
+    /// permits left.
+    permits: Mutex<usize>,
+    /// notify waiting threads of a released permit.
+    released: Condvar,
+    /// notify waiting tasks of a released permit.
+    released_async: tokio::sync::Notify,
+}
+
+/// Permit of a semaphore released on drop.
+pub struct SemaphorePermit<'a> {
+    /// semaphore of the permit.
+    semaphore: &'a Semaphore,
+}
+
+impl Semaphore {
+    /// Create a semaphore with a number of permits.
+    pub fn new(permits: usize) -> Self {
+        Self {
+            permits: Mutex::new(permits),
+            released: Condvar::new(),
+            released_async: tokio::sync::Notify::new(),
         }
     }
 
-    body
+    /// Wait for a permit.
+    pub fn acquire(&self) -> SemaphorePermit<'_> {
+        let mut permits = self.permits.lock().unwrap();
+
+        while *permits == 0 {
+            permits = self.released.wait(permits).unwrap();
+        }
+        *permits -= 1;
+
+        SemaphorePermit { semaphore: self }
+    }
+
+    /// Wait for a permit without blocking the thread of the task.
+    pub async fn acquire_async(&self) -> SemaphorePermit<'_> {
+        loop {
+            // listen before checking so a permit released in between still wakes the task
+            let released = self.released_async.notified();
+            {
+                let mut permits = self.permits.lock().unwrap();
+                if *permits > 0 {
+                    *permits -= 1;
+                    return SemaphorePermit { semaphore: self };
+                }
+            }
+            released.await;
+        }
+    }
+}
+
+impl<'a> Drop for SemaphorePermit<'a> {
+    fn drop(&mut self) {
+        *self.semaphore.permits.lock().unwrap() += 1;
+        self.semaphore.released.notify_one();
+        self.semaphore.released_async.notify_waiters();
+    }
+}
+
+/// Semaphores bounding the connections to each resolved IP address.
+#[derive(Debug)]
+pub struct IpSemaphores {
+    /// permits of each IP address.
+    permits: usize,
+    /// semaphore per resolved IP address.
+    semaphores: Mutex<HashMap<IpAddr, Arc<Semaphore>>>,
+}
+
+impl IpSemaphores {
+    /// Create semaphores with a number of permits per IP address.
+    pub fn new(permits: usize) -> Self {
+        Self {
+            permits,
+            semaphores: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Resolve the host of the URL and return the semaphore of its IP address, `None` when
+    /// the host can't be resolved.
+    pub fn semaphore(&self, url: &Url) -> Option<Arc<Semaphore>> {
+        let address = url.socket_addrs(|| None).ok()?.into_iter().next()?;
+        let mut semaphores = self.semaphores.lock().unwrap();
+        let permits = self.permits;
+
+        Some(
+            semaphores
+                .entry(address.ip())
+                .or_insert_with(|| Arc::new(Semaphore::new(permits)))
+                .clone(),
+        )
+    }
+}
+
+/// Token bucket holding a single token shared by the workers, spacing their requests evenly to
+/// a rate so bursts are smoothed.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// time to refill the token.
+    interval: Duration,
+    /// time the next token is available.
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Create a limiter of a positive number of requests per second.
+    pub fn new(requests_per_second: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / requests_per_second.max(0.001)),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Take the next token, returning the time to wait before using it.
+    pub fn reserve(&self) -> Duration {
+        let mut next = self.next.lock().unwrap();
+        let now = Instant::now();
+        let slot = (*next).max(now);
+        *next = slot + self.interval;
+
+        slot - now
+    }
+
+    /// Wait for a token.
+    pub fn acquire(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+/// Semaphores bounding the requests in flight to each host.
+#[derive(Debug)]
+pub struct HostSemaphores {
+    /// permits of each host.
+    permits: usize,
+    /// semaphore per host.
+    semaphores: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HostSemaphores {
+    /// Create semaphores with a number of permits per host.
+    pub fn new(permits: usize) -> Self {
+        Self {
+            permits,
+            semaphores: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return the semaphore of the URL host, `None` for URL without host.
+    pub fn semaphore(&self, url: &Url) -> Option<Arc<Semaphore>> {
+        let host = url.host_str()?;
+        let mut semaphores = self.semaphores.lock().unwrap();
+        let permits = self.permits;
+
+        Some(
+            semaphores
+                .entry(host.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(permits)))
+                .clone(),
+        )
+    }
 }
 
 /// log to console if configuration verbose.
@@ -29,3 +1001,377 @@ pub fn log(message: &'static str, data: impl AsRef<str>) {
         info!("{message} - {}", data.as_ref());
     }
 }
+
+#[test]
+fn test_semaphore_bound() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let semaphore = Arc::new(Semaphore::new(2));
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let semaphore = semaphore.clone();
+            let running = running.clone();
+            let max_running = max_running.clone();
+
+            std::thread::spawn(move || {
+                let _permit = semaphore.acquire();
+                let current = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(current, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+            })
+        })
+        .collect();
+
+    handles.into_iter().for_each(|h| h.join().unwrap());
+
+    assert!(max_running.load(Ordering::SeqCst) <= 2);
+}
+
+#[cfg(test)]
+/// Serve local http responses for tests, returns the server base url.
+pub(crate) fn mock_server<F>(handler: F) -> String
+where
+    F: Fn(&str) -> String + Send + Sync + 'static,
+{
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    let handler = Arc::new(handler);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let handler = handler.clone();
+
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut buffer = [0; 8192];
+                let size = stream.read(&mut buffer).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..size]).to_string();
+
+                stream.write_all(handler(&request).as_bytes()).ok();
+            });
+        }
+    });
+
+    address
+}
+
+#[cfg(test)]
+/// Serve local https responses with a self-signed `localhost` certificate and the TLS `versions`
+/// for tests, returns the server base url.
+pub(crate) fn mock_tls_server<F>(versions: &[&'static rustls::SupportedProtocolVersion], handler: F) -> String
+where
+    F: Fn(&str) -> String + Send + Sync + 'static,
+{
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    let certificate = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+    let config = rustls::ServerConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(versions)
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![rustls::Certificate(certificate.serialize_der().unwrap())],
+            rustls::PrivateKey(certificate.serialize_private_key_der()),
+        )
+        .unwrap();
+    let config = Arc::new(config);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("https://localhost:{}", listener.local_addr().unwrap().port());
+    let handler = Arc::new(handler);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let handler = handler.clone();
+            let config = config.clone();
+
+            std::thread::spawn(move || {
+                let connection = rustls::ServerConnection::new(config).unwrap();
+                let mut stream = rustls::StreamOwned::new(connection, stream);
+                let mut buffer = [0; 8192];
+                let size = stream.read(&mut buffer).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..size]).to_string();
+
+                stream.write_all(handler(&request).as_bytes()).ok();
+                stream.conn.send_close_notify();
+                stream.flush().ok();
+            });
+        }
+    });
+
+    address
+}
+
+#[cfg(test)]
+/// Build a raw http response for the mock server.
+pub(crate) fn mock_response(status: &str, headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        headers,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+/// Build a raw `200 OK` html response for the mock server.
+pub(crate) fn mock_html(body: &str) -> String {
+    mock_response("200 OK", "Content-Type: text/html\r\n", body)
+}
+
+#[test]
+fn test_fetch_page_retries() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let count = requests.clone();
+    let address = mock_server(move |request| {
+        if request.starts_with("GET /missing ") {
+            count.fetch_add(1, Ordering::SeqCst);
+            mock_response("404 Not Found", "", "")
+        } else if count.fetch_add(1, Ordering::SeqCst) < 2 {
+            mock_response("503 Service Unavailable", "", "")
+        } else {
+            mock_html("<p>ok</p>")
+        }
+    });
+    let client = Client::new();
+    let mut configuration = Configuration::new();
+    configuration.max_retries = 3;
+    configuration.retry_backoff = Duration::from_millis(10);
+
+    let res = fetch_page(&Url::parse(&address).unwrap(), &client, &configuration).unwrap();
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body, "<p>ok</p>");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    requests.store(0, Ordering::SeqCst);
+    let res = fetch_page(&Url::parse(&format!("{}/missing", address)).unwrap(), &client, &configuration).unwrap();
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    requests.store(0, Ordering::SeqCst);
+    configuration.max_retries = 1;
+    let res = fetch_page(&Url::parse(&address).unwrap(), &client, &configuration).unwrap();
+    assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_cache_vary() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    lazy_static! {
+        static ref CONDITIONAL: AtomicUsize = AtomicUsize::new(0);
+    }
+    let address = mock_server(|request| {
+        let request = request.to_lowercase();
+        let language = if request.contains("accept-language: fr") { "fr" } else { "en" };
+        let etag = format!("ETag: \"{}\"\r\nVary: Accept-Language\r\n", language);
+
+        if request.contains(&format!("if-none-match: \"{}\"", language)) {
+            CONDITIONAL.fetch_add(1, Ordering::SeqCst);
+            mock_response("304 Not Modified", &etag, "")
+        } else {
+            mock_response("200 OK", &format!("Content-Type: text/html\r\n{}", etag), language)
+        }
+    });
+    let url = Url::parse(&address).unwrap();
+    let cache_dir = std::env::temp_dir().join(format!("spider_cache_vary_{}", url.port().unwrap()));
+    let _ = fs::remove_dir_all(&cache_dir);
+    let client = Client::new();
+
+    for _ in 0..2 {
+        for language in ["en", "fr"].iter() {
+            let mut configuration = Configuration::new();
+            configuration.cache_dir = Some(cache_dir.clone());
+            configuration.headers.insert(reqwest::header::ACCEPT_LANGUAGE, HeaderValue::from_static(language));
+
+            let res = fetch_page(&url, &client, &configuration).unwrap();
+            assert_eq!(res.status, StatusCode::OK);
+            assert_eq!(res.body, *language);
+        }
+    }
+    let bodies = fs::read_dir(&cache_dir)
+        .unwrap()
+        .filter(|entry| entry.as_ref().unwrap().path().extension().map_or(false, |e| e == "body"))
+        .count();
+
+    assert_eq!(CONDITIONAL.load(Ordering::SeqCst), 2);
+    assert_eq!(bodies, 2);
+    let _ = fs::remove_dir_all(&cache_dir);
+}
+
+#[test]
+fn test_retry_after() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    lazy_static! {
+        static ref REQUESTS: AtomicUsize = AtomicUsize::new(0);
+    }
+    let address = mock_server(|_| {
+        if REQUESTS.fetch_add(1, Ordering::SeqCst) == 0 {
+            mock_response("429 Too Many Requests", "Retry-After: 2\r\n", "")
+        } else {
+            mock_html("<p>ok</p>")
+        }
+    });
+    let mut configuration = Configuration::new();
+    configuration.max_retries = 1;
+    let start = Instant::now();
+    let res = fetch_page(&Url::parse(&address).unwrap(), &Client::new(), &configuration).unwrap();
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(REQUESTS.load(Ordering::SeqCst), 2);
+    assert!(start.elapsed() >= Duration::from_secs(2));
+
+    let mut unavailable = PageResponse { status: StatusCode::SERVICE_UNAVAILABLE, ..Default::default() };
+    let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+    unavailable.headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+    let delay = retry_after(&Ok(unavailable.clone())).unwrap();
+    assert!(delay > Duration::from_secs(28) && delay <= Duration::from_secs(30), "{:?}", delay);
+
+    unavailable.status = StatusCode::INTERNAL_SERVER_ERROR;
+    assert_eq!(retry_after(&Ok(unavailable)), None);
+}
+
+#[test]
+fn test_retry_backoff() {
+    let base = Duration::from_millis(100);
+
+    assert_eq!(retry_backoff(Duration::from_millis(0), 3, 1.0), Duration::from_millis(0));
+    assert!(retry_backoff(base, 0, 1.0) >= base && retry_backoff(base, 0, 1.0) <= base * 2);
+    assert!(retry_backoff(base, 2, 1.0) >= base * 4 && retry_backoff(base, 2, 1.0) <= base * 5);
+    assert_eq!(retry_backoff(base, 2, 0.0), base * 4);
+}
+
+#[test]
+fn test_retry_jitter() {
+    use std::collections::HashMap as StdHashMap;
+
+    lazy_static! {
+        static ref RETRIES: Mutex<StdHashMap<String, Vec<Instant>>> = Mutex::new(StdHashMap::new());
+    }
+    let address = mock_server(|request| {
+        let path = request.split_whitespace().nth(1).unwrap_or_default().to_string();
+        let mut retries = RETRIES.lock().unwrap();
+        let times = retries.entry(path).or_insert_with(Vec::new);
+        times.push(Instant::now());
+        if times.len() == 1 {
+            mock_response("503 Service Unavailable", "", "")
+        } else {
+            mock_html("")
+        }
+    });
+    let mut configuration = Configuration::new();
+    configuration.max_retries = 1;
+    configuration.retry_backoff = Duration::from_millis(200);
+    configuration.retry_jitter = 1.0;
+    let handles: Vec<_> = (0..5)
+        .map(|i| {
+            let url = Url::parse(&format!("{}/{}", address, i)).unwrap();
+            let configuration = configuration.clone();
+            std::thread::spawn(move || fetch_page(&url, &Client::new(), &configuration).unwrap().status)
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), StatusCode::OK);
+    }
+    let retries = RETRIES.lock().unwrap();
+    let retried_at: Vec<Instant> = retries.values().map(|times| times[1]).collect();
+    let spread = *retried_at.iter().max().unwrap() - *retried_at.iter().min().unwrap();
+
+    assert_eq!(retried_at.len(), 5);
+    assert!(spread >= Duration::from_millis(20), "{:?}", spread);
+}
+
+#[test]
+fn test_registrable_domain() {
+    assert_eq!(registrable_domain("blog.example.co.uk"), Some("example.co.uk".to_string()));
+    assert_eq!(registrable_domain("a.b.example.com"), Some("example.com".to_string()));
+    assert_eq!(registrable_domain("example.com"), Some("example.com".to_string()));
+    assert_eq!(registrable_domain("co.uk"), None);
+}
+
+#[test]
+fn test_head_unsupported() {
+    let address = mock_server(|request| {
+        if request.starts_with("HEAD ") {
+            mock_response("405 Method Not Allowed", "", "")
+        } else if request.to_lowercase().contains("range: bytes=0-0") {
+            mock_response("206 Partial Content", "Content-Type: application/pdf\r\n", "%")
+        } else {
+            mock_response("500 Internal Server Error", "", "")
+        }
+    });
+    let client = Client::new();
+    let url = Url::parse(&format!("{}/file.pdf", address)).unwrap();
+
+    assert_eq!(fetch_status(&url, &client), Ok(StatusCode::OK));
+    let res = head_precheck(&url, &client, None).expect("classified as html");
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get(CONTENT_TYPE).unwrap(), "application/pdf");
+}
+
+#[test]
+fn test_simhash() {
+    let text = |stamp: &str| {
+        let words: Vec<String> = (0..300).map(|i| format!("word{}", i)).collect();
+        format!("{} updated {}", words.join(" "), stamp)
+    };
+
+    assert_eq!(simhash(&text("10:00")), simhash(&text("10:00")));
+    assert!((simhash(&text("10:00")) ^ simhash(&text("10:01"))).count_ones() <= 6);
+    assert!((simhash(&text("10:00")) ^ simhash("an entirely different page")).count_ones() > 6);
+}
+
+#[test]
+fn test_canonicalize() {
+    let url = |s: &str| canonicalize(&Url::parse(s).unwrap()).to_string();
+
+    assert_eq!(url("https://example.com/x?"), "https://example.com/x");
+    assert_eq!(url("https://example.com/x?a=1"), "https://example.com/x?a=1");
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("https://example.com/admin/*", "https://example.com/admin/users/1"));
+    assert!(!glob_match("https://example.com/admin/*", "https://example.com/blog/admin"));
+    assert!(glob_match("https://*/private/*", "https://docs.example.com/private/report.pdf"));
+    assert!(glob_match("https://example.com/page-?.html", "https://example.com/page-2.html"));
+    assert!(!glob_match("https://example.com/page-?.html", "https://example.com/page-12.html"));
+    assert!(glob_match("https://example.com/*", "https://example.com/"));
+}
+
+#[test]
+fn test_canonical_trailing_slash() {
+    let url = |s: &str| canonical_trailing_slash(&Url::parse(s).unwrap()).to_string();
+
+    assert_eq!(url("https://x.com/page/"), "https://x.com/page");
+    assert_eq!(url("https://x.com/page"), "https://x.com/page");
+    assert_eq!(url("https://x.com/page/?a=1"), "https://x.com/page?a=1");
+    assert_eq!(url("https://x.com/"), "https://x.com/");
+    assert_eq!(url("file:///tmp/docs/"), "file:///tmp/docs/");
+}
+
+#[test]
+fn test_clamp_concurrency() {
+    assert_eq!(clamp_concurrency(64, Some(32)), 16);
+    assert_eq!(clamp_concurrency(8, Some(1024)), 8);
+    assert_eq!(clamp_concurrency(8, Some(1)), 1);
+    assert_eq!(clamp_concurrency(8, None), 8);
+}
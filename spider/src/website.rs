@@ -1,35 +1,49 @@
 use crate::black_list::contains;
+use crate::white_list::allows;
 use crate::configuration::Configuration;
-use crate::configuration::FollowLinks;
+use crate::configuration::{CheckpointTrigger, FollowLinks, FrontierOverflow};
 use crate::page::Page;
-use crate::utils::{log};
+use crate::sitemap::{fetch_sitemap_urls, fetch_sitemap_urls_async, robots_sitemaps};
+#[cfg(test)]
+use crate::utils::{mock_html, mock_response, mock_server, mock_tls_server};
+use crate::utils::{canonical_trailing_slash, clamp_concurrency, fd_limit, fetch_page_html, fetch_page_html_async, fetch_status, is_fetchable, log, registrable_domain, tls_handshake, HostSemaphores, IpSemaphores, RateLimiter, Semaphore, TlsInfo};
 use reqwest::blocking::{Client};
+use reqwest::cookie::Jar;
+use reqwest::StatusCode;
 use rayon::ThreadPool;
 use rayon::ThreadPoolBuilder;
 use robotparser_fork::RobotFileParser;
-use hashbrown::HashSet;
-use std::{time::{Duration}};
+use hashbrown::{HashMap, HashSet};
+use std::{fmt, fs, io, io::Write, path::Path, time::{Duration, Instant}};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Sender, Receiver};
-use reqwest::header::CONNECTION;
+use reqwest::header::{CONNECTION, HOST};
 use reqwest::header;
 use tokio::time::sleep;
+use serde::Serialize;
+use serde_json::json;
 use url::Url;
+use percent_encoding::percent_decode_str;
+use rand::Rng;
+use unicode_normalization::UnicodeNormalization;
 
 /// Represents a website to crawl and gather all links.
 /// ```rust
 /// use spider::website::Website;
 /// let mut localhost = Website::new("http://example.com");
-/// localhost.crawl();
+/// localhost.crawl().unwrap();
 /// // `Website` will be filled with `Pages` when crawled. To get them, just use
 /// for page in localhost.get_pages() {
 ///     // do something
 /// }
 /// ```
-#[derive(Debug)]
 pub struct Website<'a> {
     /// configuration properties for website.
     pub configuration: Configuration,
     /// this is a start URL given when instanciate with `new`.
+    /// An empty path is normalized to `/` as for every link, so `https://w.com` is `https://w.com/`.
     pub domain: Url,
     /// contains all non-visited URL.
     links: HashSet<Url>,
@@ -37,16 +51,265 @@ pub struct Website<'a> {
     links_visited: HashSet<Url>,
     /// contains page visited
     pages: Vec<Page>,
-    /// callback when a link is found.
-    pub on_link_find_callback: fn(Url) -> Url,
+    /// callback when a link is found, shared by the crawl workers so it may capture state.
+    pub on_link_find_callback: Arc<dyn Fn(Url) -> Url + Send + Sync>,
+    /// callback when the crawl stops because no links are left to visit, with the reason.
+    pub on_frontier_empty: Option<FrontierEmptyCallback>,
+    /// callback with the body of each fetched page, the body is not stored by the crawl.
+    pub on_body: Option<BodyCallback>,
+    /// callback with each scraped page as soon as it is fetched, before it is stored.
+    pub on_page_callback: Option<PageCallback>,
     /// Robot.txt parser holder.
     robot_file_parser: RobotFileParser<'a>,
+    /// Robot.txt parsers per host origin fetched with `prefetch_robots`.
+    robots_parsers: HashMap<String, RobotFileParser<'a>>,
+    /// tally of distinct fetch error descriptions.
+    errors: HashMap<String, usize>,
+    /// TLS details per crawled host.
+    tls_info: HashMap<String, TlsInfo>,
+    /// predicates all links must pass to be followed, in the order added.
+    filters: Vec<LinkFilter>,
+    /// custom canonical key used to dedup links.
+    dedup_key: Option<DedupKey>,
+    /// dedup keys of all visited URL when a custom key is set.
+    visited_keys: HashSet<String>,
+    /// last visit time of the visited URL when `revisit_after` is set.
+    visited_at: HashMap<Url, Instant>,
+    /// number of links in scope found on each visited page.
+    link_counts: HashMap<Url, usize>,
+    /// start time of the current crawl, right before its first page fetch.
+    crawl_started: Option<Instant>,
+    /// first failed page of the current crawl when `fail_fast` is set.
+    crawl_error: Option<CrawlError>,
+    /// depth of the links found from the start URL.
+    depths: HashMap<Url, usize>,
+    /// number of links enqueued per depth when `max_breadth_per_level` is set.
+    level_breadths: HashMap<usize, usize>,
+    /// discovery order of the links found.
+    discovery_order: HashMap<Url, usize>,
+    /// accumulated share of deep dives when `depth_breadth_ratio` is set.
+    depth_credit: f64,
+    /// summary of every visited page.
+    page_summaries: HashMap<String, PageSummary>,
+    /// hosts of all the links found.
+    discovered_hosts: HashSet<String>,
+    /// all the links found on the visited pages.
+    links_found: HashSet<Url>,
+    /// next request time of the hosts with a robots.txt crawl delay.
+    host_slots: HashMap<String, Instant>,
+    /// set when the receiver of `scrape_streaming` is dropped to stop the scrape.
+    cancelled: Arc<AtomicBool>,
+    /// sender of the `(source, target)` link edges found when `edge_stream` is used.
+    edge_sender: Option<Sender<(Url, Url)>>,
+    /// fetches sampled with `capture_sample`.
+    captures: Vec<CapturedExchange>,
+    /// number of visited pages and time of the last checkpoint.
+    last_checkpoint: Option<(usize, Instant)>,
+    /// SimHash fingerprints of the pages visited when `near_dup_threshold` is set.
+    simhashes: Vec<u64>,
+    /// `Sitemap:` entries of the robots.txt of the start URL host once fetched.
+    sitemaps: Option<Vec<Url>>,
+    /// redirects not followed per page by `allowed_redirect_hosts` with their target.
+    out_of_scope_redirects: HashMap<Url, Url>,
+    /// output sinks written by the scrapes.
+    sinks: Vec<Box<dyn OutputSink>>,
+    /// number of new links found on the last pages when `max_fanout` is set.
+    fanouts: VecDeque<usize>,
+    /// `(page, resource)` pairs of the `http:` resources of `https:` pages when `detect_mixed_content` is set.
+    mixed_content: Vec<(Url, Url)>,
+    /// cookies shared by the http clients when `enable_cookies` is set.
+    cookie_jar: Arc<Jar>,
+    /// blacklist and whitelist regexes compiled when the crawl starts.
+    #[cfg(feature = "regex")]
+    url_patterns: Option<(regex::RegexSet, regex::RegexSet)>,
 }
 
-type Message = HashSet<Url>;
+impl<'a> fmt::Debug for Website<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Website")
+            .field("configuration", &self.configuration)
+            .field("domain", &self.domain)
+            .field("links", &self.links)
+            .field("links_visited", &self.links_visited)
+            .field("pages", &self.pages)
+            .field("robot_file_parser", &self.robot_file_parser)
+            .field("robots_parsers", &self.robots_parsers)
+            .field("errors", &self.errors)
+            .field("tls_info", &self.tls_info)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A node of the visited URL path hierarchy, each path segment being a node.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UrlTreeNode {
+    /// path segment of the node, the host for the root.
+    pub name: String,
+    /// nodes of the sub paths sorted by name.
+    pub children: Vec<UrlTreeNode>,
+}
+
+impl UrlTreeNode {
+    /// insert the path segments below the node.
+    fn insert(&mut self, segments: &[&str]) {
+        if let Some((first, rest)) = segments.split_first() {
+            let position = match self.children.iter().position(|c| c.name == *first) {
+                Some(position) => position,
+                None => {
+                    self.children.push(UrlTreeNode {
+                        name: first.to_string(),
+                        children: Vec::new(),
+                    });
+                    self.children.len() - 1
+                }
+            };
+            self.children[position].insert(rest);
+        }
+    }
+
+    /// sort the children recursively.
+    fn sort(&mut self) {
+        self.children.sort_by(|a, b| a.name.cmp(&b.name));
+        self.children.iter_mut().for_each(|c| c.sort());
+    }
+}
+
+/// The full request and response of a fetch sampled with `capture_sample`.
+#[derive(Debug, Clone)]
+pub struct CapturedExchange {
+    /// URL fetched.
+    pub url: String,
+    /// headers sent with the request besides the ones added by the http client.
+    pub request_headers: header::HeaderMap,
+    /// status code of the response, `None` when the request failed before a response.
+    pub status: Option<StatusCode>,
+    /// headers of the response.
+    pub response_headers: header::HeaderMap,
+    /// body of the response.
+    pub body: String,
+}
+
+/// What stopped a crawl with a `CrawlError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrawlErrorKind {
+    /// a page failed with `fail_fast` set.
+    Page,
+    /// a blacklist or whitelist pattern is malformed.
+    InvalidUrlPattern,
+    /// the pages averaged more new links than `max_fanout`.
+    FanoutExceeded,
+    /// the `proxy` is malformed, no request is sent.
+    InvalidProxy,
+    /// the http client cannot be built from the configuration, no request is sent.
+    Client,
+}
+
+/// The failure stopping a crawl, a failed page when `fail_fast` is set or an invalid configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrawlError {
+    /// what stopped the crawl.
+    pub kind: CrawlErrorKind,
+    /// URL of the failed page, the start URL for configuration errors.
+    pub url: String,
+    /// response status, `None` on transport error.
+    pub status: Option<StatusCode>,
+    /// transport error description.
+    pub error: Option<String>,
+}
+
+impl fmt::Display for CrawlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.error, self.status) {
+            (Some(error), _) => write!(f, "{}: {}", self.url, error),
+            (None, Some(status)) => write!(f, "{}: {}", self.url, status),
+            (None, None) => write!(f, "{}", self.url),
+        }
+    }
+}
+
+impl std::error::Error for CrawlError {}
+
+/// Destination of the pages scraped, registered with `Website::add_sink`.
+pub trait OutputSink: Send {
+    /// write a page as soon as it is fetched.
+    fn write_page(&mut self, page: &Page);
+    /// flush the output once the scrape ends.
+    fn finish(&mut self);
+}
+
+type Message = (Page, HashSet<Url>);
+
+/// Callback with the reason the crawl stopped because no links are left to visit.
+pub type FrontierEmptyCallback = Box<dyn Fn(&str) + Send + Sync>;
+
+/// Callback with the URL and the body of each fetched page.
+pub type BodyCallback = Box<dyn Fn(&Url, &[u8]) + Send + Sync>;
+
+/// Callback with each scraped page.
+pub type PageCallback = Box<dyn Fn(&Page) + Send + Sync>;
+
+/// Predicate a link must pass to be followed.
+pub type LinkFilter = Box<dyn Fn(&Url) -> bool + Send + Sync>;
+
+/// Canonical key of a link, links with the same key are the same page.
+pub type DedupKey = Box<dyn Fn(&Url) -> String + Send + Sync>;
+
+/// Summary of a visited page.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PageSummary {
+    /// URL of the page.
+    pub url: String,
+    /// response status code, `None` on transport error.
+    pub status: Option<u16>,
+    /// transport error description.
+    pub error: Option<String>,
+    /// number of links found on the page.
+    pub links: usize,
+}
+
+/// Counters of a crawl.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrawlStats {
+    /// number of visited URL.
+    pub visited: usize,
+    /// number of links left to visit.
+    pub pending: usize,
+    /// number of pages failing with a transport error.
+    pub errors: usize,
+    /// number of broken links.
+    pub broken: usize,
+    /// average number of in scope links per visited page.
+    pub branching_factor: f64,
+}
+
+/// Serializable outcome of a crawl, produced by `Website::report`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrawlReport {
+    /// counters of the crawl.
+    pub stats: CrawlStats,
+    /// visited URL sorted.
+    pub visited: Vec<String>,
+    /// pages failing with a transport error or a 4xx/5xx status sorted by URL.
+    pub broken_links: Vec<PageSummary>,
+    /// hosts of the links found sorted.
+    pub discovered_hosts: Vec<String>,
+    /// summary of every visited page sorted by URL.
+    pub pages: Vec<PageSummary>,
+}
+
+/// Timeout of the client requests when `request_timeout` is not set.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Redirects followed by the client before failing, like the default policy.
+const MAX_REDIRECTS: usize = 10;
+/// Number of last pages the new links are averaged over for `max_fanout`.
+const FANOUT_WINDOW: usize = 10;
 
 impl<'a> Website<'a> {
     /// Initialize Website object with a start link to crawl.
+    ///
+    /// The start link is parsed like discovered links: a root without path gets the `/` path and
+    /// other paths are kept as given. `https://w.com` is visited and reported as `https://w.com/`
+    /// while `https://w.com/docs` stays `https://w.com/docs`.
     pub fn new(domain: &str) -> Self {
         let url = Url::parse(domain).expect("Cannot parse URL");
         let mut links = HashSet::new();
@@ -56,12 +319,57 @@ impl<'a> Website<'a> {
             links_visited: HashSet::new(),
             pages: Vec::new(),
             robot_file_parser: RobotFileParser::new(&format!("{}/robots.txt", domain)), // TODO: lazy establish
+            robots_parsers: HashMap::new(),
             links,
-            on_link_find_callback: |s| s,
+            on_link_find_callback: Arc::new(|s| s),
+            on_frontier_empty: None,
+            on_body: None,
+            on_page_callback: None,
+            errors: HashMap::new(),
+            tls_info: HashMap::new(),
+            filters: Vec::new(),
+            dedup_key: None,
+            visited_keys: HashSet::new(),
+            visited_at: HashMap::new(),
+            link_counts: HashMap::new(),
+            crawl_started: None,
+            crawl_error: None,
+            depths: HashMap::new(),
+            level_breadths: HashMap::new(),
+            discovery_order: HashMap::new(),
+            depth_credit: 0.0,
+            page_summaries: HashMap::new(),
+            discovered_hosts: HashSet::new(),
+            links_found: HashSet::new(),
+            host_slots: HashMap::new(),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            edge_sender: None,
+            captures: Vec::new(),
+            last_checkpoint: None,
+            simhashes: Vec::new(),
+            sitemaps: None,
+            out_of_scope_redirects: HashMap::new(),
+            sinks: Vec::new(),
+            fanouts: VecDeque::new(),
+            mixed_content: Vec::new(),
+            cookie_jar: Arc::new(Jar::default()),
+            #[cfg(feature = "regex")]
+            url_patterns: None,
             domain: url,
         }
     }
 
+    /// Initialize Website object crawling a local directory of html files as a site.
+    /// Links are resolved against the file paths and files are read instead of requested.
+    pub fn new_local<P: AsRef<Path>>(path: P) -> Self {
+        let path = fs::canonicalize(path).expect("Cannot read directory");
+        let url = Url::from_directory_path(path).expect("Cannot parse directory path");
+        let mut website = Website::new(url.as_str());
+        website.configuration.delay = 0;
+
+        website
+    }
+
     /// page getter
     pub fn get_pages(&self) -> Vec<Page> {
         if !self.pages.is_empty(){
@@ -76,326 +384,3609 @@ impl<'a> Website<'a> {
         &self.links_visited
     }
 
-    /// crawl delay getter
-    fn get_delay(&self) -> Duration {
-        Duration::from_millis(self.configuration.delay)
-    }
+    /// build the tree of visited URL paths.
+    pub fn url_tree(&self) -> UrlTreeNode {
+        let mut root = UrlTreeNode {
+            name: self.domain.host_str().unwrap_or_default().to_string(),
+            children: Vec::new(),
+        };
 
-    /// configure the robots parser on initial crawl attempt and run
-    pub fn configure_robots_parser(&mut self) {
-        if self.configuration.respect_robots_txt && self.robot_file_parser.mtime() == 0 {
-            self.robot_file_parser.user_agent = self.configuration.user_agent.to_string();
-            self.robot_file_parser.read();
-            self.configuration.delay = self
-                .robot_file_parser
-                .get_crawl_delay(&self.robot_file_parser.user_agent) // returns the crawl delay in seconds
-                .unwrap_or(self.get_delay())
-                .as_millis() as u64;
+        for link in self.links_visited.iter() {
+            let segments: Vec<&str> = link
+                .path_segments()
+                .map(|s| s.filter(|s| !s.is_empty()).collect())
+                .unwrap_or_default();
+            root.insert(&segments);
         }
+
+        root.sort();
+        root
     }
 
-    /// configure http client
-    fn configure_http_client(&mut self, user_agent: Option<String>) -> Client {
-        let mut headers = header::HeaderMap::new();
-        headers.insert(CONNECTION, header::HeaderValue::from_static("keep-alive"));
+    /// distinct fetch error descriptions encountered with their occurrence count.
+    pub fn error_summary(&self) -> HashMap<String, usize> {
+        self.errors.clone()
+    }
 
-        Client::builder()
-            .default_headers(headers)
-            .user_agent(user_agent.unwrap_or(self.configuration.user_agent.to_string()))
-            .build()
-            .expect("Failed building client.")
+    /// fetches sampled with `capture_sample` with their full request and response.
+    pub fn captures(&self) -> &[CapturedExchange] {
+        &self.captures
     }
 
-    /// configure rayon thread pool
-    fn create_thread_pool(&mut self) -> ThreadPool {
-        ThreadPoolBuilder::new()
-            .num_threads(self.configuration.concurrency)
-            .build()
-            .expect("Failed building thread pool.")
+    /// add a sink every scraped page is written to, the sinks are finished at the end of each scrape.
+    pub fn add_sink(&mut self, sink: Box<dyn OutputSink>) {
+        self.sinks.push(sink);
     }
 
-    /// setup config for crawl
-    fn setup(&mut self) -> Client {
-        self.configure_robots_parser();
-        let client = self.configure_http_client(None);
+    /// add a predicate to the filter chain, a link is only followed when every filter returns `true`.
+    pub fn add_filter(&mut self, filter: LinkFilter) {
+        self.filters.push(filter);
+    }
 
-        client
+    /// set the canonical key used to decide if two links are the same page.
+    pub fn set_dedup_key(&mut self, dedup_key: DedupKey) {
+        self.visited_keys = self.links_visited.iter().map(&dedup_key).collect();
+        self.dedup_key = Some(dedup_key);
     }
-    
-    /// Start to crawl website with async parallelization
-    pub fn crawl(&mut self) {
-        let client = self.setup();
 
-        self.crawl_concurrent(&client);
+    /// return `true` if the link or a link with the same dedup key was visited.
+    fn is_visited(&self, link: &Url) -> bool {
+        match &self.dedup_key {
+            Some(dedup_key) => self.visited_keys.contains(&dedup_key(link)),
+            None => self.links_visited.contains(link),
+        }
     }
 
-    /// Start to scrape website with async parallelization
-    pub fn scrape(&mut self) {
-        let client = self.setup();
+    /// mark a link as visited.
+    fn mark_visited(&mut self, link: &Url) {
+        self.links_visited.insert(link.to_owned());
+        if let Some(dedup_key) = &self.dedup_key {
+            self.visited_keys.insert(dedup_key(link));
+        }
+        if self.configuration.revisit_after.is_some() {
+            self.visited_at.insert(link.to_owned(), Instant::now());
+        }
+    }
 
-        self.scrape_concurrent(&client);
+    /// return `true` if the link was visited longer ago than `revisit_after`.
+    fn is_revisitable(&self, link: &Url) -> bool {
+        match (self.configuration.revisit_after, self.visited_at.get(link)) {
+            (Some(revisit_after), Some(visited_at)) => visited_at.elapsed() >= revisit_after,
+            _ => false,
+        }
     }
 
-    /// Start to crawl website in sync
-    pub fn crawl_sync(&mut self) {
-        let client = self.setup();
+    /// add the new links to the links to visit, visited links are dropped unless revisitable.
+    fn extend_frontier(&mut self, new_links: HashSet<Url>) {
+        let links = self
+            .links
+            .union(&new_links)
+            .filter(|l| !self.links_visited.contains(*l) || self.is_revisitable(l))
+            .cloned()
+            .collect();
+        self.links = links;
+    }
 
-        self.crawl_sequential(&client);
+    /// Redirects not followed because their target host is not in `allowed_redirect_hosts`,
+    /// by page with their target.
+    pub fn out_of_scope_redirects(&self) -> HashMap<Url, Url> {
+        self.out_of_scope_redirects.clone()
     }
 
-    /// Start to crawl website concurrently
-    fn crawl_concurrent(&mut self, client: &Client) {
-        let pool = self.create_thread_pool();
-        let delay = self.configuration.delay;
-        let delay_enabled = delay > 0;
-        let on_link_find_callback = self.on_link_find_callback;
-        
-        // crawl while links exists
-        while !self.links.is_empty() {
-            let (tx, rx): (Sender<Message>, Receiver<Message>) = channel();
+    /// Sitemap URL not linked from any visited page, the start URL excepted.
+    pub fn orphans(&self, sitemap_urls: &HashSet<Url>) -> HashSet<Url> {
+        sitemap_urls
+            .iter()
+            .filter(|url| {
+                let url = self.normalize((*url).clone());
+                url != self.domain && !self.links_found.contains(&url)
+            })
+            .cloned()
+            .collect()
+    }
 
-            for link in self.links.iter() {
-                if !self.is_allowed(link) {
-                    continue;
-                }
-                log("fetch", link);
+    /// `(page, resource)` pairs of the `http:` links and resources found on `https:` pages when
+    /// `detect_mixed_content` is set.
+    pub fn mixed_content(&self) -> Vec<(Url, Url)> {
+        self.mixed_content.clone()
+    }
 
-                self.links_visited.insert(link.to_owned());
+    /// TLS details captured for a host when `tls_info` is enabled.
+    /// The http client only exposes the peer certificate, not the negotiated protocol or cipher.
+    pub fn tls_info(&self, host: &str) -> Option<TlsInfo> {
+        self.tls_info.get(host).cloned()
+    }
 
-                let link = link.clone();
-                let tx = tx.clone();
-                let cx = client.clone();
+    /// average number of links in scope found per visited page.
+    pub fn branching_factor(&self) -> f64 {
+        if self.link_counts.is_empty() {
+            0.0
+        } else {
+            self.link_counts.values().sum::<usize>() as f64 / self.link_counts.len() as f64
+        }
+    }
 
-                pool.spawn(move || {
-                    if delay_enabled {
-                        tokio_sleep(&Duration::from_millis(delay));
-                    }
-                    let link_result = on_link_find_callback(link);
-                    let page = Page::new(&link_result, &cx);
-                    let links = page.links();
+    /// Report of the crawl with its stats, visited URL, broken links, discovered hosts and page summaries.
+    pub fn report(&self) -> CrawlReport {
+        let mut pages: Vec<PageSummary> = self.page_summaries.values().cloned().collect();
+        pages.sort_by(|a, b| a.url.cmp(&b.url));
+        let broken_links: Vec<PageSummary> = pages
+            .iter()
+            .filter(|p| p.error.is_some() || p.status.is_some_and(|status| status >= 400))
+            .cloned()
+            .collect();
+        let mut visited: Vec<String> = self.links_visited.iter().map(|l| l.to_string()).collect();
+        visited.sort();
+        let mut discovered_hosts: Vec<String> = self.discovered_hosts.iter().cloned().collect();
+        discovered_hosts.sort();
 
-                    tx.send(links).unwrap();
-                });
-            }
+        CrawlReport {
+            stats: CrawlStats {
+                visited: visited.len(),
+                pending: self.links.len(),
+                errors: self.errors.values().sum(),
+                broken: broken_links.len(),
+                branching_factor: self.branching_factor(),
+            },
+            visited,
+            broken_links,
+            discovered_hosts,
+            pages,
+        }
+    }
 
-            drop(tx);
+    /// visited URL grouped by the status code of their response, sorted. Pages that could not
+    /// be fetched have no status and are left out.
+    pub fn urls_by_status(&self) -> HashMap<StatusCode, Vec<Url>> {
+        let mut urls: HashMap<StatusCode, Vec<Url>> = HashMap::new();
 
-            let mut new_links: HashSet<Url> = HashSet::new();
+        for page in self.page_summaries.values().filter(|p| p.error.is_none()) {
+            let status = page.status.and_then(|status| StatusCode::from_u16(status).ok());
+            if let (Some(status), Ok(url)) = (status, Url::parse(&page.url)) {
+                urls.entry(status).or_insert_with(Vec::new).push(url);
+            }
+        }
+        urls.values_mut().for_each(|urls| urls.sort());
 
-            rx.into_iter().for_each(|links| {
-                new_links.extend(links);
-            });
+        urls
+    }
 
-            self.links = &new_links - &self.links_visited;
+    /// record the fetch outcome and the links found of a page.
+    fn record_page(&mut self, page: &Page, links: &HashSet<Url>) {
+        self.page_summaries.insert(
+            page.get_url().to_string(),
+            PageSummary {
+                url: page.get_url().to_string(),
+                status: page.get_status_code().map(|status| status.as_u16()),
+                error: page.get_error().cloned(),
+                links: links.len(),
+            },
+        );
+        self.discovered_hosts.extend(links.iter().filter_map(|l| l.host_str().map(str::to_string)));
+        self.links_found.extend(links.iter().cloned());
+        if let Ok(url) = Url::parse(page.get_url()) {
+            let count = links.iter().filter(|l| self.is_in_scope(l)).count();
+            self.link_counts.insert(url, count);
+        }
+        if let Some(error) = page.get_error() {
+            *self.errors.entry(error.to_owned()).or_insert(0) += 1;
+        }
+        if self.configuration.detect_mixed_content {
+            if let Ok(url) = Url::parse(page.get_url()) {
+                let mut resources: Vec<Url> = page.mixed_content().into_iter().collect();
+                resources.sort();
+                self.mixed_content.extend(resources.into_iter().map(|resource| (url.clone(), resource)));
+            }
+        }
+        if let (Some(tls_info), Ok(url)) = (page.get_tls_info(), Url::parse(page.get_url())) {
+            if let Some(host) = url.host_str().filter(|host| !self.tls_info.contains_key(*host)) {
+                let mut tls_info = tls_info.clone();
+                // the handshake would bypass the proxy
+                if self.configuration.proxy.is_none() {
+                    let timeout = self.configuration.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+                    if let Some((protocol, cipher)) = tls_handshake(&url, timeout) {
+                        tls_info.protocol = Some(protocol);
+                        tls_info.cipher = Some(cipher);
+                    }
+                }
+                self.tls_info.insert(host.to_string(), tls_info);
+            }
+        }
+        if self.configuration.allowed_redirect_hosts.is_some() && page.get_status_code().is_some_and(|status| status.is_redirection()) {
+            let location = page.get_headers().get(header::LOCATION).and_then(|l| l.to_str().ok());
+            if let (Ok(url), Some(location)) = (Url::parse(page.get_url()), location) {
+                if let Ok(target) = url.join(location) {
+                    log("- redirect out of scope {}", format!("{} -> {}", url, target));
+                    self.out_of_scope_redirects.insert(url, target);
+                }
+            }
+        }
+        if let Some(capture_sample) = self.configuration.capture_sample {
+            if rand::random::<f64>() < capture_sample {
+                let mut request_headers = self.default_headers();
+                if let Ok(user_agent) = header::HeaderValue::from_str(&self.configuration.user_agent) {
+                    request_headers.insert(header::USER_AGENT, user_agent);
+                }
+                self.captures.push(CapturedExchange {
+                    url: page.get_url().to_string(),
+                    request_headers,
+                    status: page.get_status_code(),
+                    response_headers: page.get_headers().clone(),
+                    body: page.get_html().to_string(),
+                });
+            }
+        }
+        if self.configuration.fail_fast && self.crawl_error.is_none() {
+            let status = page.get_status_code().filter(|status| status.is_client_error() || status.is_server_error());
+            if page.get_error().is_some() {
+                self.crawl_error = Some(CrawlError {
+                    kind: CrawlErrorKind::Page,
+                    url: page.get_url().to_string(),
+                    status: None,
+                    error: page.get_error().cloned(),
+                });
+            } else if status.is_some() {
+                self.crawl_error = Some(CrawlError {
+                    kind: CrawlErrorKind::Page,
+                    url: page.get_url().to_string(),
+                    status,
+                    error: None,
+                });
+            }
         }
     }
 
-    /// Start to crawl website sequential
-    fn crawl_sequential(&mut self, client: &Client) {
-        let delay = self.configuration.delay;
-        let delay_enabled = delay > 0;
-        let on_link_find_callback = self.on_link_find_callback;
-        
-        // crawl while links exists
-        while !self.links.is_empty() {
-            let mut new_links: HashSet<Url> = HashSet::new();
+    /// take the error stopping the last crawl.
+    fn crawl_result(&mut self) -> Result<(), CrawlError> {
+        match self.crawl_error.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
 
-            for link in self.links.iter() {
-                if !self.is_allowed(link) {
-                    continue;
-                }
-                log("fetch", link);
-                self.links_visited.insert(link.to_owned());
-                if delay_enabled {
-                    tokio_sleep(&Duration::from_millis(delay));
+    /// crawl delay getter
+    fn get_delay(&self) -> Duration {
+        Duration::from_millis(self.configuration.delay)
+    }
+
+    /// configure the robots parser on initial crawl attempt and run
+    pub fn configure_robots_parser(&mut self) {
+        if self.configuration.respect_robots_txt && self.robot_file_parser.mtime() == 0 {
+            self.robot_file_parser.user_agent = self.configuration.user_agent.to_string();
+            if !self.robots_parsers.contains_key(&self.domain.origin().ascii_serialization()) {
+                self.robot_file_parser.read();
+            }
+            self.configuration.delay = self
+                .robots_crawl_delay(&self.domain)
+                .unwrap_or(self.get_delay())
+                .as_millis() as u64;
+        }
+    }
+
+    /// Fetch and cache the robots.txt parsers of the given hosts concurrently, before crawling.
+    /// Hosts are origins like `https://docs.example.com` or bare hosts using the scheme of the start URL.
+    pub fn prefetch_robots(&mut self, hosts: &[&str]) {
+        let client = match self.configure_http_client(None) {
+            Ok(client) => client,
+            Err(error) => return log("- error building client", error.to_string()),
+        };
+        let origins: Vec<Url> = hosts
+            .iter()
+            .filter_map(|host| {
+                if host.contains("://") {
+                    Url::parse(host).ok()
+                } else {
+                    Url::parse(&format!("{}://{}", self.domain.scheme(), host)).ok()
                 }
+            })
+            .collect();
 
-                let link = link.clone();
-                let cx = client.clone();
-                let link_result = on_link_find_callback(link);
-                let page = Page::new(&link_result, &cx);
-                let links = page.links();
+        let handles: Vec<_> = origins
+            .into_iter()
+            .map(|origin| {
+                let client = client.clone();
+                std::thread::spawn(move || {
+                    let robots_url = origin.join("/robots.txt").expect("Cannot parse URL");
+                    let body = fetch_page_html(&robots_url, &client).unwrap_or_default();
+                    (origin, robots_url, body)
+                })
+            })
+            .collect();
 
-                new_links.extend(links);
+        for handle in handles {
+            if let Ok((origin, robots_url, body)) = handle.join() {
+                self.insert_robots_parser(&origin, &robots_url, &body);
             }
+        }
+    }
 
-            self.links = &new_links - &self.links_visited;
+    /// cache the robots parser of a host from its robots.txt body.
+    fn insert_robots_parser(&mut self, origin: &Url, robots_url: &Url, body: &str) {
+        let mut robot_file_parser = RobotFileParser::new(robots_url.as_str());
+        robot_file_parser.user_agent = self.configuration.user_agent.to_string();
+        let lines: Vec<&str> = body.lines().collect();
+        robot_file_parser.parse(&lines);
+        if origin.origin() == self.domain.origin() {
+            self.sitemaps = Some(robots_sitemaps(body));
         }
+        self.robots_parsers.insert(origin.origin().ascii_serialization(), robot_file_parser);
     }
 
-    /// Start to scape website concurrently and store html
-    fn scrape_concurrent(&mut self, client: &Client) {
-        let pool = self.create_thread_pool();
-        let delay = self.configuration.delay;
-        let delay_enabled = delay > 0;
-        let on_link_find_callback = self.on_link_find_callback;
-        
-        // crawl while links exists
-        while !self.links.is_empty() {
-            let (tx, rx): (Sender<Page>, Receiver<Page>) = channel();
+    /// delay before fetching a link, the requests of hosts declaring a crawl delay in their
+    /// prefetched robots.txt are spaced by their own delay.
+    fn link_delay(&mut self, link: &Url) -> Duration {
+        let origin = link.origin().ascii_serialization();
+        let crawl_delay = if self.configuration.respect_robots_txt && self.robots_parsers.contains_key(&origin) {
+            self.robots_crawl_delay(link)
+        } else {
+            None
+        };
 
-            for link in self.links.iter() {
-                if !self.is_allowed(link) {
-                    continue;
-                }
-                log("fetch", link);
+        match crawl_delay {
+            Some(crawl_delay) => {
+                let now = Instant::now();
+                let slot = self.host_slots.get(&origin).copied().filter(|slot| *slot > now).unwrap_or(now);
+                self.host_slots.insert(origin, slot + crawl_delay);
+                slot - now
+            }
+            None => self.polite_delay(),
+        }
+    }
 
-                self.links_visited.insert(link.to_owned());
+    /// polite delay before a request, drawn uniformly from `delay_jitter` when set.
+    fn polite_delay(&self) -> Duration {
+        match self.configuration.delay_jitter {
+            Some((min, max)) => Duration::from_millis(rand::thread_rng().gen_range(min.min(max)..=max.max(min))),
+            None => self.get_delay(),
+        }
+    }
 
-                let link = link.clone();
-                let tx = tx.clone();
-                let cx = client.clone();
+    /// crawl delay declared in the robots.txt of the URL host for the configured user agent,
+    /// falling back to the delay of the `*` group when the agent group declares none.
+    fn robots_crawl_delay(&self, link: &Url) -> Option<Duration> {
+        let robot_file_parser = self.robots_parser(link);
 
-                pool.spawn(move || {
-                    if delay_enabled {
-                        tokio_sleep(&Duration::from_millis(delay));
-                    }
-                    let link_result = on_link_find_callback(link);
-                    let page = Page::new(&link_result, &cx);
+        robot_file_parser
+            .get_crawl_delay(&self.configuration.user_agent)
+            .or_else(|| robot_file_parser.get_crawl_delay("*"))
+    }
 
-                    tx.send(page).unwrap();
-                });
-            }
+    /// robots parser of the URL host, the prefetched one if any.
+    fn robots_parser(&self, link: &Url) -> &RobotFileParser<'a> {
+        self.robots_parsers
+            .get(&link.origin().ascii_serialization())
+            .unwrap_or(&self.robot_file_parser)
+    }
 
-            drop(tx);
+    /// default headers of the http clients, the configured `headers` override the defaults.
+    fn default_headers(&self) -> header::HeaderMap {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(CONNECTION, header::HeaderValue::from_static("keep-alive"));
 
-            let mut new_links: HashSet<Url> = HashSet::new();
+        if let Some(host) = &self.configuration.host_header {
+            headers.insert(HOST, header::HeaderValue::from_str(host).expect("Invalid host header."));
+        }
+        headers.extend(self.configuration.headers.clone());
 
-            rx.into_iter().for_each(|page| {
-                let links = page.links();
-                new_links.extend(links);
-                self.pages.push(page);
-            });
+        headers
+    }
 
-            self.links = &new_links - &self.links_visited;
+    /// error of the configuration stopping a crawl before it starts.
+    fn setup_error(&self, kind: CrawlErrorKind, error: String) -> CrawlError {
+        CrawlError {
+            kind,
+            url: self.domain.to_string(),
+            status: None,
+            error: Some(error),
         }
     }
-    
-    /// return `true` if URL:
-    ///
-    /// - is not already crawled
-    /// - is not blacklisted
-    /// - is not forbidden in robot.txt file (if parameter is defined)  
-    pub fn is_allowed(&self, link: &Url) -> bool {
-        if self.links_visited.contains(link) {
-            return false;
+
+    /// proxy of the http clients with its credentials.
+    fn proxy(&self) -> Result<Option<reqwest::Proxy>, CrawlError> {
+        let proxy = match &self.configuration.proxy {
+            Some(proxy) => reqwest::Proxy::all(proxy)
+                .map_err(|error| self.setup_error(CrawlErrorKind::InvalidProxy, format!("invalid proxy: {}", error)))?,
+            None => return Ok(None),
+        };
+
+        Ok(Some(match &self.configuration.proxy_auth {
+            Some((username, password)) => proxy.basic_auth(username, password),
+            None => proxy,
+        }))
+    }
+
+    /// configure async http client
+    fn configure_async_http_client(&self) -> Result<reqwest::Client, CrawlError> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(self.configuration.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT))
+            .use_rustls_tls()
+            .tls_info(self.configuration.tls_info)
+            .user_agent(self.configuration.user_agent.to_string())
+            .default_headers(self.default_headers())
+            .redirect(self.redirect_policy());
+
+        if let Some(min_tls_version) = self.configuration.min_tls_version {
+            builder = builder.min_tls_version(min_tls_version);
         }
-        if contains(&self.configuration.blacklist_url, link) {
-            return false;
+        if self.configuration.accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
         }
-        if self.configuration.respect_robots_txt && !self.is_allowed_robots(link) {
-            return false;
+        if let Some(proxy) = self.proxy()? {
+            builder = builder.proxy(proxy);
         }
-        return match &self.configuration.follow_links
-        {
-            FollowLinks::NONE        => false,
-            FollowLinks::HOSTNAME    => link.domain() == self.domain.domain(),
-            FollowLinks::SUBDOMAINS  => false,
-            FollowLinks::SAMEDOMAIN  => false,
-            FollowLinks::ALL         => true
+        if self.configuration.enable_cookies {
+            builder = builder.cookie_provider(self.cookie_jar.clone());
         }
+
+        builder
+            .build()
+            .map_err(|error| self.setup_error(CrawlErrorKind::Client, format!("failed building client: {}", error)))
     }
 
+    /// follow redirects within a host and to the `allowed_redirect_hosts`.
+    fn redirect_policy(&self) -> reqwest::redirect::Policy {
+        let allowed_hosts = match &self.configuration.allowed_redirect_hosts {
+            Some(allowed_hosts) => allowed_hosts.clone(),
+            None => return reqwest::redirect::Policy::default(),
+        };
 
-    /// return `true` if URL:
-    ///
-    /// - is not forbidden in robot.txt file (if parameter is defined)  
-    pub fn is_allowed_robots(&self, link: &Url) -> bool {
-        self.robot_file_parser.can_fetch("*", &link.to_string())
+        reqwest::redirect::Policy::custom(move |attempt| {
+            let host = attempt.url().host_str().map(|h| h.to_lowercase());
+            let previous_host = attempt.previous().last().and_then(|p| p.host_str()).map(|h| h.to_lowercase());
+
+            if attempt.previous().len() > MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if host == previous_host
+                || host.is_some_and(|host| allowed_hosts.iter().any(|a| a.eq_ignore_ascii_case(&host)))
+            {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        })
     }
-}
 
-impl<'a> Drop for Website<'a> {
-    fn drop(&mut self) {}
-}
+    /// configure http client
+    fn configure_http_client(&mut self, user_agent: Option<String>) -> Result<Client, CrawlError> {
+        let mut builder = Client::builder()
+            .timeout(self.configuration.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT))
+            .use_rustls_tls()
+            .tls_info(self.configuration.tls_info)
+            .user_agent(user_agent.unwrap_or(self.configuration.user_agent.to_string()))
+            .default_headers(self.default_headers())
+            .redirect(self.redirect_policy());
 
-// blocking sleep keeping thread alive
-#[tokio::main]
-async fn tokio_sleep(delay: &Duration){
-    sleep(*delay).await;
-}
+        if let Some(max_conns_per_ip) = self.configuration.max_conns_per_ip {
+            builder = builder.pool_max_idle_per_host(max_conns_per_ip);
+        }
+        if let Some(min_tls_version) = self.configuration.min_tls_version {
+            builder = builder.min_tls_version(min_tls_version);
+        }
+        if self.configuration.accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        if let Some(proxy) = self.proxy()? {
+            builder = builder.proxy(proxy);
+        }
+        if self.configuration.enable_cookies {
+            builder = builder.cookie_provider(self.cookie_jar.clone());
+        }
 
-#[test]
-fn crawl() {
-    let mut website: Website = Website::new("https://choosealicense.com");
-    website.crawl();
-    assert!(
-        website
-            .links_visited
-            .contains(&"https://choosealicense.com/licenses/".to_string()),
-        "{:?}",
-        website.links_visited
-    );
-}
+        builder
+            .build()
+            .map_err(|error| self.setup_error(CrawlErrorKind::Client, format!("failed building client: {}", error)))
+    }
 
-#[test]
-fn scrape() {
-    let mut website: Website = Website::new("https://choosealicense.com");
-    website.scrape();
-    assert!(
-        website
-            .links_visited
-            .contains(&"https://choosealicense.com/licenses/".to_string()),
-        "{:?}",
-        website.links_visited
-    );
+    /// configure rayon thread pool
+    fn create_thread_pool(&mut self) -> ThreadPool {
+        let concurrency = clamp_concurrency(self.configuration.concurrency, fd_limit());
 
-    assert_eq!(
-        website.get_pages()[0].get_html().is_empty(),
+        if concurrency < self.configuration.concurrency {
+            log("concurrency clamped to file descriptor limit", concurrency.to_string());
+        }
+
+        ThreadPoolBuilder::new()
+            .num_threads(concurrency)
+            .build()
+            .expect("Failed building thread pool.")
+    }
+
+    /// setup config for crawl, an invalid client configuration fails before any request.
+    fn setup(&mut self) -> Result<Client, CrawlError> {
+        let client = self.configure_http_client(None)?;
+        self.prepare();
+
+        if self.configuration.crawl_sitemap {
+            self.seed_sitemaps(&client);
+        }
+
+        Ok(client)
+    }
+
+    /// Sitemaps listed with `Sitemap:` in the robots.txt of the start URL host, fetched once.
+    pub fn get_sitemaps(&mut self) -> Vec<Url> {
+        match &self.sitemaps {
+            Some(sitemaps) => sitemaps.clone(),
+            None => match self.configure_http_client(None) {
+                Ok(client) => self.robots_sitemaps(&client),
+                Err(_) => Vec::new(),
+            },
+        }
+    }
+
+    /// fetch the `Sitemap:` entries of the robots.txt of the start URL host unless known.
+    fn robots_sitemaps(&mut self, client: &Client) -> Vec<Url> {
+        if self.sitemaps.is_none() {
+            let robots_url = self.domain.join("/robots.txt").expect("Cannot parse URL");
+            let robots = fetch_page_html(&robots_url, client).unwrap_or_default();
+            self.sitemaps = Some(robots_sitemaps(&robots));
+        }
+
+        self.sitemaps.clone().unwrap_or_default()
+    }
+
+    /// add the pages listed by the sitemaps of the start URL host to the links to visit.
+    fn seed_sitemaps(&mut self, client: &Client) {
+        self.robots_sitemaps(client);
+
+        for sitemap in self.sitemap_files() {
+            let links = fetch_sitemap_urls(&sitemap, client);
+            self.seed_links(links);
+        }
+    }
+
+    /// add the pages listed by the sitemaps of the start URL host to the links to visit without
+    /// blocking the runtime.
+    async fn seed_sitemaps_async(&mut self, client: &reqwest::Client) {
+        if self.sitemaps.is_none() {
+            let robots_url = self.domain.join("/robots.txt").expect("Cannot parse URL");
+            let robots = fetch_page_html_async(&robots_url, client).await.unwrap_or_default();
+            self.sitemaps = Some(robots_sitemaps(&robots));
+        }
+
+        for sitemap in self.sitemap_files() {
+            let links = fetch_sitemap_urls_async(&sitemap, client).await;
+            self.seed_links(links);
+        }
+    }
+
+    /// `/sitemap.xml` followed by the `Sitemap:` entries of the robots.txt not already listed.
+    fn sitemap_files(&self) -> Vec<Url> {
+        let mut sitemaps = vec![self.domain.join("/sitemap.xml").expect("Cannot parse URL")];
+
+        for sitemap in self.sitemaps.iter().flatten() {
+            if !sitemaps.contains(sitemap) {
+                sitemaps.push(sitemap.clone());
+            }
+        }
+
+        sitemaps
+    }
+
+    /// add the sitemap links to the links to visit, visited links are skipped unless revisitable.
+    fn seed_links(&mut self, links: Vec<Url>) {
+        for link in links {
+            let link = self.normalize(link);
+            if !self.links_visited.contains(&link) || self.is_revisitable(&link) {
+                self.links.insert(link);
+            }
+        }
+    }
+
+    /// reset the crawl state before a crawl
+    fn prepare(&mut self) {
+        #[cfg(feature = "regex")]
+        self.compile_url_patterns();
+        self.configure_robots_parser();
+        if self.links.is_empty() {
+            // re-crawl from the start, visited links are skipped unless revisitable
+            self.links.insert(self.domain.clone());
+        }
+        self.links = self.links.iter().map(|l| self.normalize(l.clone())).collect();
+    }
+    
+    /// compile the blacklist and whitelist regexes once, a malformed pattern stops the crawl with an error.
+    #[cfg(feature = "regex")]
+    fn compile_url_patterns(&mut self) {
+        use crate::black_list::compile;
+
+        match (compile(&self.configuration.blacklist_url), compile(&self.configuration.whitelist_url)) {
+            (Ok(blacklist), Ok(whitelist)) => self.url_patterns = Some((blacklist, whitelist)),
+            (Err(error), _) | (_, Err(error)) => {
+                self.url_patterns = None;
+                self.crawl_error = Some(self.setup_error(CrawlErrorKind::InvalidUrlPattern, format!("invalid url pattern: {}", error)));
+            }
+        }
+    }
+
+    /// Start to crawl website with async parallelization.
+    /// Returns the first failed page when `fail_fast` is set.
+    pub fn crawl(&mut self) -> Result<(), CrawlError> {
+        if self.configuration.async_runtime {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed building runtime.");
+
+            return runtime.block_on(self.crawl_async());
+        }
+        let client = self.setup()?;
+
+        self.crawl_concurrent(&client);
+        self.crawl_result()
+    }
+
+    /// Continue a crawl stopped by `max_pages` from its frontier, visiting up to `additional_pages`
+    /// more pages. `max_pages` is left unchanged.
+    pub fn continue_crawl(&mut self, additional_pages: usize) -> Result<(), CrawlError> {
+        let max_pages = self.configuration.max_pages;
+        self.configuration.max_pages = Some(self.links_visited.len() + additional_pages);
+        let result = self.crawl();
+        self.configuration.max_pages = max_pages;
+
+        result
+    }
+
+    /// Start to crawl website with async requests on the current tokio runtime, `concurrency`
+    /// bounds the requests in flight and delays do not block threads.
+    /// Returns the first failed page when `fail_fast` is set.
+    pub async fn crawl_async(&mut self) -> Result<(), CrawlError> {
+        let client = self.configure_async_http_client()?;
+
+        if self.configuration.respect_robots_txt
+            && self.robot_file_parser.mtime() == 0
+            && !self.robots_parsers.contains_key(&self.domain.origin().ascii_serialization())
+        {
+            // fetch the robots.txt without blocking the runtime
+            let origin = self.domain.clone();
+            let robots_url = origin.join("/robots.txt").expect("Cannot parse URL");
+            let body = fetch_page_html_async(&robots_url, &client).await.unwrap_or_default();
+            self.insert_robots_parser(&origin, &robots_url, &body);
+        }
+        self.prepare();
+        if self.configuration.crawl_sitemap {
+            self.seed_sitemaps_async(&client).await;
+        }
+        self.crawl_concurrent_async(&client).await;
+        self.crawl_result()
+    }
+
+    /// Start to scrape website with async parallelization.
+    /// Returns the first failed page when `fail_fast` is set.
+    pub fn scrape(&mut self) -> Result<(), CrawlError> {
+        let client = self.setup()?;
+
+        self.scrape_concurrent(&client, &mut |_| true);
+        self.crawl_result()
+    }
+
+    /// Start to scrape website with async parallelization only storing the pages matching the predicate.
+    /// Links of every page are still followed.
+    pub fn scrape_filtered(&mut self, predicate: Box<dyn Fn(&Page) -> bool>) -> Result<(), CrawlError> {
+        let client = self.setup()?;
+
+        self.scrape_concurrent(&client, &mut |page| predicate(page));
+        self.crawl_result()
+    }
+
+    /// Start to scrape website writing one JSON object per page with its url, status (`null` when the
+    /// request failed before a response), title and links count to the writer as each page is
+    /// fetched. Pages are not stored.
+    pub fn scrape_ndjson<W: Write>(&mut self, mut writer: W) -> io::Result<()> {
+        let client = self.setup().map_err(io::Error::other)?;
+        let mut result = Ok(());
+
+        self.scrape_concurrent(&client, &mut |page| {
+            if result.is_ok() {
+                let line = json!({
+                    "url": page.get_url(),
+                    "status": page.get_status_code().map(|status| status.as_u16()),
+                    "title": page.title(),
+                    "links": page.links().len(),
+                });
+                result = writeln!(writer, "{}", line).and_then(|_| writer.flush());
+            }
+            false
+        });
+        self.crawl_result().map_err(io::Error::other)?;
+
+        result
+    }
+
+    /// Start to scrape website on a background thread sending each page on the returned channel
+    /// as soon as it is fetched. Pages are not stored, the channel closes when the crawl ends
+    /// and dropping the receiver stops the crawl after the pages in flight.
+    pub fn scrape_streaming(mut self) -> Receiver<Page>
+    where
+        'a: 'static,
+    {
+        let (tx, rx) = channel();
+
+        std::thread::spawn(move || {
+            let client = match self.setup() {
+                Ok(client) => client,
+                Err(error) => return log("- error building client", error.to_string()),
+            };
+            let cancelled = self.cancelled.clone();
+
+            self.scrape_concurrent(&client, &mut |page| {
+                if tx.send(page.clone()).is_err() {
+                    cancelled.store(true, Ordering::Relaxed);
+                }
+                false
+            });
+        });
+
+        rx
+    }
+
+    /// Start to scrape website only storing the pages not listed in the newline-delimited file of
+    /// previously seen URL. The file is updated with every URL visited for the next run.
+    pub fn crawl_new_since<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let seen: HashSet<String> = match fs::read_to_string(path) {
+            Ok(content) => content.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashSet::new(),
+            Err(e) => return Err(e),
+        };
+        let prior = seen.clone();
+
+        self.scrape_filtered(Box::new(move |page| !prior.contains(page.get_url())))
+            .map_err(io::Error::other)?;
+
+        let mut urls: Vec<String> = self.links_visited.iter().map(|l| l.to_string()).collect();
+        urls.extend(seen.into_iter().filter(|l| Url::parse(l).map_or(true, |u| !self.links_visited.contains(&u))));
+        urls.sort();
+
+        fs::write(path, urls.join("\n"))
+    }
+
+    /// Check every link of a single page, external ones included, without crawling further.
+    /// Returns the status of each link, links that could not be requested are left out.
+    pub fn check_page(&mut self, url: &str) -> Vec<(Url, StatusCode)> {
+        let client = match self.configure_http_client(None) {
+            Ok(client) => client,
+            Err(_) => return Vec::new(),
+        };
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(_) => return Vec::new(),
+        };
+        let page = Page::new(&url, &client);
+        let mut checked: HashSet<Url> = HashSet::new();
+
+        page.all_hrefs()
+            .into_iter()
+            .filter_map(|(_, link)| link)
+            .filter(|link| matches!(link.scheme(), "http" | "https") && checked.insert(link.clone()))
+            .filter_map(|link| match fetch_status(&link, &client) {
+                Ok(status) => Some((link, status)),
+                Err(e) => {
+                    log("- error checking link {}", format!("{} {}", link, e));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Stream the `(source, target)` link edges of the next crawls as the pages are fetched.
+    /// The channel closes when the website is dropped or `edge_stream` is called again.
+    pub fn edge_stream(&mut self) -> Receiver<(Url, Url)> {
+        let (tx, rx) = channel();
+        self.edge_sender = Some(tx);
+
+        rx
+    }
+
+    /// Restore the crawl state from the newline-delimited frontier and visited files
+    /// written with `frontier_file` and `visited_file`. Visited URL are not crawled again.
+    pub fn resume_from_files<P: AsRef<Path>>(&mut self, frontier_path: P, visited_path: P) -> io::Result<()> {
+        let read_urls = |path: &Path| -> io::Result<HashSet<Url>> {
+            Ok(fs::read_to_string(path)?
+                .lines()
+                .filter_map(|l| Url::parse(l.trim()).ok())
+                .collect())
+        };
+        let visited = read_urls(visited_path.as_ref())?;
+        let frontier = read_urls(frontier_path.as_ref())?;
+
+        if let Some(dedup_key) = &self.dedup_key {
+            self.visited_keys.extend(visited.iter().map(dedup_key));
+        }
+        self.links = &frontier - &visited;
+        self.links_visited.extend(visited);
+
+        Ok(())
+    }
+
+    /// return `true` while discovered links can be enqueued within the `discovery_window`.
+    fn is_discovering(&self) -> bool {
+        match (self.configuration.discovery_window, self.crawl_started) {
+            (Some(window), Some(started)) => started.elapsed() < window,
+            _ => true,
+        }
+    }
+
+    /// return `true` once the crawl visited `max_pages` pages.
+    fn is_page_limit_reached(&self) -> bool {
+        self.configuration.max_pages.is_some_and(|max_pages| self.links_visited.len() >= max_pages)
+    }
+
+    /// enqueue the links found on a page within the discovery window and the depth limit.
+    fn discover(&mut self, page: &Page, links: HashSet<Url>, new_links: &mut HashSet<Url>) {
+        self.send_edges(page, &links);
+        if !self.is_discovering() || self.is_near_duplicate(page) {
+            return;
+        }
+        let depth = Url::parse(page.get_url())
+            .ok()
+            .and_then(|url| self.depths.get(&url).copied())
+            .unwrap_or(0)
+            + 1;
+        if self.configuration.max_depth.is_some_and(|max_depth| depth > max_depth) {
+            return;
+        }
+        let mut fanout = 0;
+        for link in links {
+            if !self.depths.contains_key(&link) {
+                if self.is_level_full(depth) {
+                    continue;
+                }
+                fanout += 1;
+            }
+            let order = self.discovery_order.len();
+            self.discovery_order.entry(link.clone()).or_insert(order);
+            self.depths.entry(link.clone()).or_insert(depth);
+            new_links.insert(link);
+        }
+        self.check_fanout(page, fanout);
+    }
+
+    /// stop the crawl with an error when the new links per page averaged over the last
+    /// `FANOUT_WINDOW` pages exceed `max_fanout`, usually a crawl trap or a too wide scope.
+    fn check_fanout(&mut self, page: &Page, fanout: usize) {
+        let max_fanout = match self.configuration.max_fanout {
+            Some(max_fanout) => max_fanout,
+            None => return,
+        };
+        self.fanouts.push_back(fanout);
+        if self.fanouts.len() > FANOUT_WINDOW {
+            self.fanouts.pop_front();
+        }
+        let average = self.fanouts.iter().sum::<usize>() as f64 / self.fanouts.len() as f64;
+
+        if self.fanouts.len() == FANOUT_WINDOW && average > max_fanout && self.crawl_error.is_none() {
+            log("- fanout exceeded {}", page.get_url());
+            self.crawl_error = Some(CrawlError {
+                kind: CrawlErrorKind::FanoutExceeded,
+                url: page.get_url().to_string(),
+                status: None,
+                error: Some(format!(
+                    "fanout exceeded: {:.1} new links per page over the last {} pages, max {}",
+                    average, FANOUT_WINDOW, max_fanout
+                )),
+            });
+        }
+    }
+
+    /// return `true` once `max_breadth_per_level` links were enqueued at the depth, counting the link otherwise.
+    fn is_level_full(&mut self, depth: usize) -> bool {
+        let max_breadth = match self.configuration.max_breadth_per_level {
+            Some(max_breadth) => max_breadth,
+            None => return false,
+        };
+        let breadth = self.level_breadths.entry(depth).or_insert(0);
+
+        if *breadth >= max_breadth {
+            true
+        } else {
+            *breadth += 1;
+            false
+        }
+    }
+
+    /// return `true` if the page is within `near_dup_threshold` of a page already visited,
+    /// the fingerprint of a distinct page is kept.
+    fn is_near_duplicate(&mut self, page: &Page) -> bool {
+        let threshold = match self.configuration.near_dup_threshold {
+            Some(threshold) if !page.get_html().is_empty() => threshold,
+            _ => return false,
+        };
+        let fingerprint = page.simhash();
+
+        if self.simhashes.iter().any(|seen| (seen ^ fingerprint).count_ones() <= threshold) {
+            log("- near duplicate {}", page.get_url());
+            true
+        } else {
+            self.simhashes.push(fingerprint);
+            false
+        }
+    }
+
+    /// return `true` if the workers keep the html of the pages for the crawl to use it.
+    fn keeps_html(&self) -> bool {
+        self.on_body.is_some()
+            || self.configuration.capture_sample.is_some()
+            || self.configuration.near_dup_threshold.is_some()
+            || self.configuration.detect_mixed_content
+    }
+
+    /// send the links of a page to the `edge_stream` receiver, the stream is closed once it is dropped.
+    fn send_edges(&mut self, page: &Page, links: &HashSet<Url>) {
+        let sender = match &self.edge_sender {
+            Some(sender) => sender,
+            None => return,
+        };
+        let source = match Url::parse(page.get_url()) {
+            Ok(source) => source,
+            Err(_) => return,
+        };
+        let closed = links.iter().any(|link| sender.send((source.clone(), link.clone())).is_err());
+
+        if closed {
+            self.edge_sender = None;
+        }
+    }
+
+    /// drop the links exceeding `max_frontier` by discovery order.
+    fn cap_frontier(&mut self) {
+        let max_frontier = match self.configuration.max_frontier {
+            Some(max_frontier) if self.links.len() > max_frontier => max_frontier,
+            _ => return,
+        };
+        let discovery_order = &self.discovery_order;
+        let mut links: Vec<Url> = self.links.drain().collect();
+        links.sort_by_key(|link| discovery_order.get(link).copied().unwrap_or(0));
+
+        match self.configuration.frontier_overflow {
+            FrontierOverflow::DropNewest => links.truncate(max_frontier),
+            FrontierOverflow::DropOldest => {
+                links.drain(..links.len() - max_frontier);
+            }
+        }
+        log("frontier capped", max_frontier.to_string());
+
+        self.links = links.into_iter().collect();
+    }
+
+    /// take the links of the next round, every link or a batch scheduled by `depth_breadth_ratio`.
+    fn next_frontier(&mut self, batch: usize) -> HashSet<Url> {
+        let ratio = match self.configuration.depth_breadth_ratio {
+            Some(ratio) => ratio.clamp(0.0, 1.0),
+            None => return std::mem::take(&mut self.links),
+        };
+        let mut frontier = HashSet::new();
+
+        while frontier.len() < batch.max(1) && !self.links.is_empty() {
+            self.depth_credit += ratio;
+            let deep_dive = self.depth_credit >= 1.0;
+            if deep_dive {
+                self.depth_credit -= 1.0;
+            }
+            // rank by depth then discovery order
+            let (depths, discovery_order) = (&self.depths, &self.discovery_order);
+            let rank = |link: &&Url| {
+                (
+                    depths.get(*link).copied().unwrap_or(0),
+                    discovery_order.get(*link).copied().unwrap_or(0),
+                )
+            };
+            let next = if deep_dive {
+                self.links.iter().max_by_key(rank)
+            } else {
+                self.links.iter().min_by_key(rank)
+            }
+            .cloned();
+
+            if let Some(link) = next {
+                self.links.remove(&link);
+                frontier.insert(link);
+            }
+        }
+
+        frontier
+    }
+
+    /// run the body callback on a fetched page.
+    fn notify_body(&self, page: &Page) {
+        if let Some(on_body) = &self.on_body {
+            if let Ok(url) = Url::parse(page.get_url()) {
+                on_body(&url, page.get_html().as_bytes());
+            }
+        }
+    }
+
+    /// run the frontier callback if the crawl ended with no links left.
+    fn notify_frontier_empty(&self) {
+        if self.links.is_empty() {
+            if let Some(on_frontier_empty) = &self.on_frontier_empty {
+                on_frontier_empty("frontier exhausted");
+            }
+        }
+    }
+
+    /// append the links visited from the frontier and the next frontier to the state files.
+    fn append_state_files(&self, frontier: &HashSet<Url>) {
+        let append = |path: &Path, links: Vec<&Url>| -> io::Result<()> {
+            let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+            for link in links {
+                writeln!(file, "{}", link)?;
+            }
+            Ok(())
+        };
+
+        if let Some(path) = &self.configuration.visited_file {
+            let visited = frontier.iter().filter(|l| self.links_visited.contains(*l)).collect();
+            if append(path, visited).is_err() {
+                log("- error writing visited file {}", path.to_string_lossy());
+            }
+        }
+        if let Some(path) = &self.configuration.frontier_file {
+            if append(path, self.links.iter().collect()).is_err() {
+                log("- error writing frontier file {}", path.to_string_lossy());
+            }
+        }
+    }
+
+    /// Save the links left to visit and the visited links to a JSON file.
+    pub fn save_state<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let sorted = |links: &HashSet<Url>| {
+            let mut links: Vec<String> = links.iter().map(|l| l.to_string()).collect();
+            links.sort_unstable();
+            links
+        };
+        let state = json!({
+            "frontier": sorted(&self.links),
+            "visited": sorted(&self.links_visited),
+        });
+        let path = path.as_ref();
+        let partial = path.with_extension("partial");
+
+        // replace the previous state at once so an interrupted save keeps it intact
+        fs::write(&partial, state.to_string())?;
+        fs::rename(&partial, path)
+    }
+
+    /// save the crawl state when `checkpoint_every` is due.
+    fn checkpoint(&mut self) {
+        let (trigger, path) = match (self.configuration.checkpoint_every, &self.configuration.checkpoint_file) {
+            (Some(trigger), Some(path)) => (trigger, path.clone()),
+            _ => return,
+        };
+        let (pages, at) = self
+            .last_checkpoint
+            .unwrap_or((0, self.crawl_started.unwrap_or_else(Instant::now)));
+        let visited = self.links_visited.len();
+        let due = match trigger {
+            CheckpointTrigger::Pages(every) => visited >= pages + every.max(1),
+            CheckpointTrigger::Interval(every) => at.elapsed() >= every,
+        };
+
+        if due {
+            if self.save_state(&path).is_err() {
+                log("- error writing checkpoint file {}", path.to_string_lossy());
+            }
+            self.last_checkpoint = Some((visited, Instant::now()));
+        }
+    }
+
+    /// Start to crawl website in sync.
+    /// Returns the first failed page when `fail_fast` is set.
+    pub fn crawl_sync(&mut self) -> Result<(), CrawlError> {
+        let client = self.setup()?;
+
+        self.crawl_sequential(&client);
+        self.crawl_result()
+    }
+
+    /// Start to crawl website concurrently
+    fn crawl_concurrent(&mut self, client: &Client) {
+        let pool = self.create_thread_pool();
+        let on_link_find_callback = self.on_link_find_callback.clone();
+        let configuration = Arc::new(self.configuration.clone());
+        let parse_permits = self.configuration.parse_concurrency.map(|permits| Arc::new(Semaphore::new(permits)));
+        let ip_permits = self.configuration.max_conns_per_ip.map(|permits| Arc::new(IpSemaphores::new(permits)));
+        let host_permits = self.configuration.max_concurrent_per_host.map(|permits| Arc::new(HostSemaphores::new(permits)));
+        let rate_limiter = self.configuration.requests_per_second.map(|rate| Arc::new(RateLimiter::new(rate)));
+        let keep_html = self.keeps_html();
+        
+        self.crawl_started = Some(Instant::now());
+
+        // crawl while links exists
+        while !self.links.is_empty() && self.crawl_error.is_none() && !self.is_page_limit_reached() {
+            let (tx, rx): (Sender<Message>, Receiver<Message>) = channel();
+
+            let frontier = self.next_frontier(self.configuration.concurrency);
+
+            for link in frontier.iter() {
+                if self.is_page_limit_reached() {
+                    // keep the links left for `continue_crawl`
+                    self.links.insert(link.clone());
+                    continue;
+                }
+                if !self.is_allowed(link) {
+                    continue;
+                }
+                log("fetch", link);
+
+                self.mark_visited(link);
+
+                let delay = self.link_delay(link);
+                let link = link.clone();
+                let tx = tx.clone();
+                let cx = client.clone();
+                let configuration = configuration.clone();
+                let parse_permits = parse_permits.clone();
+                let ip_permits = ip_permits.clone();
+                let host_permits = host_permits.clone();
+                let rate_limiter = rate_limiter.clone();
+                let on_link_find_callback = on_link_find_callback.clone();
+
+                pool.spawn(move || {
+                    if !delay.is_zero() {
+                        std::thread::sleep(delay);
+                    }
+                    if let Some(rate_limiter) = &rate_limiter {
+                        rate_limiter.acquire();
+                    }
+                    let link_result = on_link_find_callback(link);
+                    let mut page = {
+                        let host_semaphore = host_permits.as_ref().and_then(|permits| permits.semaphore(&link_result));
+                        let _host_permit = host_semaphore.as_ref().map(|semaphore| semaphore.acquire());
+                        let ip_semaphore = ip_permits.as_ref().and_then(|permits| permits.semaphore(&link_result));
+                        let _permit = ip_semaphore.as_ref().map(|semaphore| semaphore.acquire());
+                        Page::new_with_configuration(&link_result, &cx, &configuration)
+                    };
+                    let links = {
+                        let _permit = parse_permits.as_ref().map(|permits| permits.acquire());
+                        page.links_with_configuration(&configuration)
+                    };
+                    if !keep_html {
+                        page.clear_html();
+                    }
+
+                    tx.send((page, links)).unwrap();
+                });
+            }
+
+            drop(tx);
+
+            let mut new_links: HashSet<Url> = HashSet::new();
+
+            rx.into_iter().for_each(|(page, links)| {
+                if self.crawl_error.is_some() {
+                    return;
+                }
+                self.notify_body(&page);
+                let links: HashSet<Url> = links.into_iter().map(|l| self.normalize(l)).collect();
+                self.record_page(&page, &links);
+                self.discover(&page, links, &mut new_links);
+            });
+
+            self.extend_frontier(new_links);
+            self.cap_frontier();
+            self.append_state_files(&frontier);
+            self.checkpoint();
+        }
+
+        self.notify_frontier_empty();
+    }
+
+    /// Start to crawl website concurrently with async requests on the current runtime
+    async fn crawl_concurrent_async(&mut self, client: &reqwest::Client) {
+        let on_link_find_callback = self.on_link_find_callback.clone();
+        let configuration = Arc::new(self.configuration.clone());
+        let permits = Arc::new(tokio::sync::Semaphore::new(clamp_concurrency(self.configuration.concurrency, fd_limit())));
+        let ip_permits = self.configuration.max_conns_per_ip.map(|permits| Arc::new(IpSemaphores::new(permits)));
+        let host_permits = self.configuration.max_concurrent_per_host.map(|permits| Arc::new(HostSemaphores::new(permits)));
+        let rate_limiter = self.configuration.requests_per_second.map(|rate| Arc::new(RateLimiter::new(rate)));
+        let keep_html = self.keeps_html();
+
+        self.crawl_started = Some(Instant::now());
+
+        // crawl while links exists
+        while !self.links.is_empty() && self.crawl_error.is_none() && !self.is_page_limit_reached() {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Message>();
+
+            let frontier = self.next_frontier(self.configuration.concurrency);
+
+            for link in frontier.iter() {
+                if self.is_page_limit_reached() {
+                    // keep the links left for `continue_crawl`
+                    self.links.insert(link.clone());
+                    continue;
+                }
+                if !self.is_allowed(link) {
+                    continue;
+                }
+                log("fetch", link);
+
+                self.mark_visited(link);
+
+                let delay = self.link_delay(link);
+                let link = link.clone();
+                let tx = tx.clone();
+                let cx = client.clone();
+                let configuration = configuration.clone();
+                let permits = permits.clone();
+                let ip_permits = ip_permits.clone();
+                let host_permits = host_permits.clone();
+                let rate_limiter = rate_limiter.clone();
+                let on_link_find_callback = on_link_find_callback.clone();
+
+                tokio::spawn(async move {
+                    let _permit = permits.acquire_owned().await;
+                    if !delay.is_zero() {
+                        sleep(delay).await;
+                    }
+                    if let Some(rate_limiter) = &rate_limiter {
+                        sleep(rate_limiter.reserve()).await;
+                    }
+                    let link_result = on_link_find_callback(link);
+                    let mut page = {
+                        let host_semaphore = host_permits.as_ref().and_then(|permits| permits.semaphore(&link_result));
+                        let _host_permit = match &host_semaphore {
+                            Some(semaphore) => Some(semaphore.acquire_async().await),
+                            None => None,
+                        };
+                        let ip_semaphore = ip_permits.as_ref().and_then(|permits| permits.semaphore(&link_result));
+                        let _permit = match &ip_semaphore {
+                            Some(semaphore) => Some(semaphore.acquire_async().await),
+                            None => None,
+                        };
+                        Page::new_with_configuration_async(&link_result, &cx, &configuration).await
+                    };
+                    let links = page.links_with_configuration(&configuration);
+                    if !keep_html {
+                        page.clear_html();
+                    }
+
+                    let _ = tx.send((page, links));
+                });
+            }
+
+            drop(tx);
+
+            let mut new_links: HashSet<Url> = HashSet::new();
+
+            while let Some((page, links)) = rx.recv().await {
+                if self.crawl_error.is_some() {
+                    continue;
+                }
+                self.notify_body(&page);
+                let links: HashSet<Url> = links.into_iter().map(|l| self.normalize(l)).collect();
+                self.record_page(&page, &links);
+                self.discover(&page, links, &mut new_links);
+            }
+
+            self.extend_frontier(new_links);
+            self.cap_frontier();
+            self.append_state_files(&frontier);
+            self.checkpoint();
+        }
+
+        self.notify_frontier_empty();
+    }
+
+    /// Start to crawl website sequential
+    fn crawl_sequential(&mut self, client: &Client) {
+        let on_link_find_callback = self.on_link_find_callback.clone();
+        let rate_limiter = self.configuration.requests_per_second.map(RateLimiter::new);
+        
+        self.crawl_started = Some(Instant::now());
+
+        // crawl while links exists
+        while !self.links.is_empty() && self.crawl_error.is_none() && !self.is_page_limit_reached() {
+            let mut new_links: HashSet<Url> = HashSet::new();
+            let frontier = self.next_frontier(1);
+
+            for link in frontier.iter() {
+                if self.crawl_error.is_some() {
+                    break;
+                }
+                if self.is_page_limit_reached() {
+                    // keep the links left for `continue_crawl`
+                    self.links.insert(link.clone());
+                    continue;
+                }
+                if !self.is_allowed(link) {
+                    continue;
+                }
+                log("fetch", link);
+                self.mark_visited(link);
+                let delay = self.link_delay(link);
+                if !delay.is_zero() {
+                    std::thread::sleep(delay);
+                }
+                if let Some(rate_limiter) = &rate_limiter {
+                    rate_limiter.acquire();
+                }
+
+                let link = link.clone();
+                let cx = client.clone();
+                let link_result = on_link_find_callback(link);
+                let page = Page::new_with_configuration(&link_result, &cx, &self.configuration);
+                self.notify_body(&page);
+                let links = page.links_with_configuration(&self.configuration);
+
+                let links: HashSet<Url> = links.into_iter().map(|l| self.normalize(l)).collect();
+                self.record_page(&page, &links);
+                self.discover(&page, links, &mut new_links);
+            }
+
+            self.extend_frontier(new_links);
+            self.cap_frontier();
+            self.append_state_files(&frontier);
+            self.checkpoint();
+        }
+
+        self.notify_frontier_empty();
+    }
+
+    /// Start to scape website concurrently, the pages are stored when `store` returns `true`
+    fn scrape_concurrent(&mut self, client: &Client, store: &mut dyn FnMut(&Page) -> bool) {
+        let pool = self.create_thread_pool();
+        let on_link_find_callback = self.on_link_find_callback.clone();
+        let configuration = Arc::new(self.configuration.clone());
+        let ip_permits = self.configuration.max_conns_per_ip.map(|permits| Arc::new(IpSemaphores::new(permits)));
+        let host_permits = self.configuration.max_concurrent_per_host.map(|permits| Arc::new(HostSemaphores::new(permits)));
+        let rate_limiter = self.configuration.requests_per_second.map(|rate| Arc::new(RateLimiter::new(rate)));
+        
+        self.crawl_started = Some(Instant::now());
+
+        // crawl while links exists
+        while !self.links.is_empty() && self.crawl_error.is_none() && !self.is_page_limit_reached()
+            && !self.cancelled.load(Ordering::Relaxed)
+        {
+            let (tx, rx): (Sender<Page>, Receiver<Page>) = channel();
+
+            let frontier = self.next_frontier(self.configuration.concurrency);
+
+            for link in frontier.iter() {
+                if self.is_page_limit_reached() {
+                    // keep the links left for `continue_crawl`
+                    self.links.insert(link.clone());
+                    continue;
+                }
+                if !self.is_allowed(link) {
+                    continue;
+                }
+                log("fetch", link);
+
+                self.mark_visited(link);
+
+                let delay = self.link_delay(link);
+                let link = link.clone();
+                let tx = tx.clone();
+                let cx = client.clone();
+                let configuration = configuration.clone();
+                let ip_permits = ip_permits.clone();
+                let host_permits = host_permits.clone();
+                let rate_limiter = rate_limiter.clone();
+                let on_link_find_callback = on_link_find_callback.clone();
+
+                pool.spawn(move || {
+                    if !delay.is_zero() {
+                        std::thread::sleep(delay);
+                    }
+                    if let Some(rate_limiter) = &rate_limiter {
+                        rate_limiter.acquire();
+                    }
+                    let link_result = on_link_find_callback(link);
+                    let page = {
+                        let host_semaphore = host_permits.as_ref().and_then(|permits| permits.semaphore(&link_result));
+                        let _host_permit = host_semaphore.as_ref().map(|semaphore| semaphore.acquire());
+                        let ip_semaphore = ip_permits.as_ref().and_then(|permits| permits.semaphore(&link_result));
+                        let _permit = ip_semaphore.as_ref().map(|semaphore| semaphore.acquire());
+                        Page::new_with_configuration(&link_result, &cx, &configuration)
+                    };
+
+                    tx.send(page).unwrap();
+                });
+            }
+
+            drop(tx);
+
+            let mut new_links: HashSet<Url> = HashSet::new();
+
+            rx.into_iter().for_each(|page| {
+                if self.crawl_error.is_some() || self.cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
+                self.notify_body(&page);
+                let links = page.links_with_configuration(&self.configuration);
+                let links: HashSet<Url> = links.into_iter().map(|l| self.normalize(l)).collect();
+                self.record_page(&page, &links);
+                self.discover(&page, links, &mut new_links);
+                if let Some(on_page_callback) = &self.on_page_callback {
+                    on_page_callback(&page);
+                }
+                for sink in self.sinks.iter_mut() {
+                    sink.write_page(&page);
+                }
+                if store(&page) {
+                    self.pages.push(page);
+                }
+            });
+
+            self.extend_frontier(new_links);
+            self.cap_frontier();
+            self.append_state_files(&frontier);
+            self.checkpoint();
+        }
+
+        for sink in self.sinks.iter_mut() {
+            sink.finish();
+        }
+        self.notify_frontier_empty();
+    }
+    
+    /// normalize a link before it enters the frontier.
+    fn normalize(&self, link: Url) -> Url {
+        let mut link = canonical_trailing_slash(&link);
+        if !self.configuration.strip_query_params.is_empty() && link.query().is_some() {
+            let strip = |name: &str| {
+                self.configuration.strip_query_params.iter().any(|param| match param.strip_suffix('*') {
+                    Some(prefix) => name.starts_with(prefix),
+                    None => name == param,
+                })
+            };
+            let pairs: Vec<(String, String)> = link.query_pairs().into_owned().collect();
+            let kept: Vec<&(String, String)> = pairs.iter().filter(|(name, _)| !strip(name)).collect();
+            if kept.is_empty() {
+                link.set_query(None);
+            } else if kept.len() < pairs.len() {
+                link.query_pairs_mut().clear().extend_pairs(kept);
+            }
+        }
+        if self.configuration.sort_query_params && link.query().is_some() {
+            let mut pairs: Vec<(String, String)> = link.query_pairs().into_owned().collect();
+            pairs.sort();
+            link.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+        if self.configuration.unicode_normalize {
+            let decoded = percent_decode_str(link.path()).decode_utf8_lossy().to_string();
+            let normalized: String = decoded.nfc().collect();
+            if normalized != decoded {
+                link.set_path(&normalized);
+            }
+        }
+        if self.configuration.case_insensitive_paths {
+            let path = link.path().to_lowercase();
+            link.set_path(&path);
+        }
+        if self.configuration.canonicalize_mobile {
+            let desktop = link.host_str().and_then(|host| host.strip_prefix("m.")).map(|host| host.to_string());
+            if let Some(desktop) = desktop {
+                link.set_host(Some(&desktop)).ok();
+            }
+        }
+
+        link
+    }
+
+    /// return `true` if URL:
+    ///
+    /// - has a fetchable scheme
+    /// - is not already crawled
+    /// - is not blacklisted
+    /// - is whitelisted (if the whitelist is not empty)
+    /// - is not forbidden in robot.txt file (if parameter is defined)  
+    pub fn is_allowed(&self, link: &Url) -> bool {
+        if !is_fetchable(link) {
+            return false;
+        }
+        if self.is_visited(link) && !self.is_revisitable(link) {
+            return false;
+        }
+        if self.is_blacklisted(link) || !self.is_whitelisted(link) {
+            return false;
+        }
+        if !self.filters.iter().all(|filter| filter(link)) {
+            return false;
+        }
+        if self.configuration.respect_robots_txt && !self.is_allowed_robots(link) {
+            return false;
+        }
+        self.is_in_scope(link)
+    }
+
+    /// return `true` if URL matches the blacklist.
+    #[cfg(not(feature = "regex"))]
+    fn is_blacklisted(&self, link: &Url) -> bool {
+        contains(&self.configuration.blacklist_url, link)
+    }
+
+    /// return `true` if URL matches the blacklist regexes, compiled per call outside of a crawl.
+    #[cfg(feature = "regex")]
+    fn is_blacklisted(&self, link: &Url) -> bool {
+        match &self.url_patterns {
+            Some((blacklist, _)) => contains(blacklist, link),
+            None => crate::black_list::compile(&self.configuration.blacklist_url)
+                .is_ok_and(|blacklist| contains(&blacklist, link)),
+        }
+    }
+
+    /// return `true` if URL matches the whitelist or the whitelist is empty.
+    #[cfg(not(feature = "regex"))]
+    fn is_whitelisted(&self, link: &Url) -> bool {
+        allows(&self.configuration.whitelist_url, link)
+    }
+
+    /// return `true` if URL matches the whitelist regexes or the whitelist is empty.
+    #[cfg(feature = "regex")]
+    fn is_whitelisted(&self, link: &Url) -> bool {
+        match &self.url_patterns {
+            Some((_, whitelist)) => allows(whitelist, link),
+            None => crate::black_list::compile(&self.configuration.whitelist_url)
+                .map_or(true, |whitelist| allows(&whitelist, link)),
+        }
+    }
+
+    /// return `true` if URL is in the scope set by `follow_links`, the start URL always is.
+    fn is_in_scope(&self, link: &Url) -> bool {
+        if *link == self.domain || *link == self.normalize(self.domain.clone()) {
+            return true;
+        }
+        match &self.configuration.follow_links
+        {
+            FollowLinks::NONE        => false,
+            FollowLinks::HOSTNAME if self.domain.scheme() == "file" => {
+                link.scheme() == "file" && link.path().starts_with(self.domain.path())
+            }
+            FollowLinks::HOSTNAME    => link.domain() == self.domain.domain(),
+            FollowLinks::SUBDOMAINS  => {
+                let host = |url: &Url| url.domain().map(|d| d.trim_end_matches('.').to_lowercase());
+                match (host(link), host(&self.domain)) {
+                    (Some(link_host), Some(domain)) => {
+                        link_host == domain || link_host.ends_with(&format!(".{}", domain))
+                    }
+                    _ => false,
+                }
+            }
+            FollowLinks::SAMEDOMAIN  => {
+                let registrable = |url: &Url| url.domain().and_then(registrable_domain);
+                registrable(link).is_some() && registrable(link) == registrable(&self.domain)
+            }
+            FollowLinks::ALL         => true
+        }
+    }
+
+
+    /// return `true` if URL:
+    ///
+    /// - is not forbidden in robot.txt file (if parameter is defined)  
+    ///
+    /// The rules of the configured user agent apply, the `*` rules without user agent.
+    pub fn is_allowed_robots(&self, link: &Url) -> bool {
+        let user_agent = match self.configuration.user_agent.trim() {
+            "" => "*",
+            user_agent => user_agent,
+        };
+
+        self.robots_parser(link).can_fetch(user_agent, link.as_str())
+    }
+}
+
+impl<'a> Drop for Website<'a> {
+    fn drop(&mut self) {}
+}
+
+/// Mock server answering with the handler and a website of its address crawled without delay,
+/// with the server address.
+#[cfg(test)]
+fn mock_website<F>(handler: F) -> (String, Website<'static>)
+where
+    F: Fn(&str) -> String + Send + Sync + 'static,
+{
+    let address = mock_server(handler);
+    let mut website = Website::new(&address);
+    website.configuration.delay = 0;
+
+    (address, website)
+}
+
+#[test]
+fn crawl() {
+    let mut website: Website = Website::new("https://choosealicense.com");
+    website.crawl().unwrap();
+    assert!(
+        website
+            .links_visited
+            .contains(&"https://choosealicense.com/licenses".to_string()),
+        "{:?}",
+        website.links_visited
+    );
+}
+
+#[test]
+fn scrape() {
+    let mut website: Website = Website::new("https://choosealicense.com");
+    website.scrape().unwrap();
+    assert!(
+        website
+            .links_visited
+            .contains(&"https://choosealicense.com/licenses".to_string()),
+        "{:?}",
+        website.links_visited
+    );
+
+    assert_eq!(
+        website.get_pages()[0].get_html().is_empty(),
         false
     );  
 }
 
 #[test]
-fn crawl_subsequential() {
-    let mut website: Website = Website::new("https://choosealicense.com");
-    website.configuration.delay = 250;
-    website.crawl_sync();
-    assert!(
-        website
-            .links_visited
-            .contains(&"https://choosealicense.com/licenses/".to_string()),
-        "{:?}",
-        website.links_visited
-    );
+fn scrape_filtered() {
+    let (_, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET /a ") {
+            r#"<p>license</p><a href="/b">b</a>"#
+        } else if request.starts_with("GET /b ") {
+            "<p>b</p>"
+        } else {
+            r#"<a href="/a">a</a>"#
+        };
+        mock_html(body)
+    });
+    website.scrape_filtered(Box::new(|page| page.get_html().contains("license"))).unwrap();
+
+    assert_eq!(website.links_visited.len(), 3, "{:?}", website.links_visited);
+    assert_eq!(website.pages.len(), 1);
+    assert!(website.pages[0].get_url().ends_with("/a"));
+}
+
+#[test]
+fn crawl_new_since() {
+    let address = mock_server(|_| {
+        mock_html(r#"<a href="/a">a</a><a href="/b">b</a>"#)
+    });
+    let path = std::env::temp_dir().join("spider_crawl_new_since.txt");
+    fs::write(&path, format!("{}/\n{}/a\n", address, address)).unwrap();
+
+    let mut website: Website = Website::new(&address);
+    website.crawl_new_since(&path).unwrap();
+    let pages: Vec<String> = website.pages.iter().map(|p| p.get_url().to_string()).collect();
+
+    assert_eq!(pages, vec![format!("{}/b", address)]);
+    assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 3);
+
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn crawl_url_tree() {
+    let (_, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET /a ") {
+            r#"<a href="/a/b">b</a>"#
+        } else if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a><a href="/c">c</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.crawl().unwrap();
+    let tree = website.url_tree();
+    let names: Vec<&str> = tree.children.iter().map(|c| c.name.as_str()).collect();
+
+    assert_eq!(tree.name, "127.0.0.1");
+    assert_eq!(names, vec!["a", "c"]);
+    assert_eq!(tree.children[0].children[0].name, "b");
+    assert!(tree.children[1].children.is_empty());
+}
+
+#[test]
+fn scrape_retry_empty_body() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let requests = AtomicUsize::new(0);
+    let (_, mut website) = mock_website(move |_| {
+        let body = if requests.fetch_add(1, Ordering::SeqCst) == 0 { "" } else { "<p>content</p>" };
+        mock_html(body)
+    });
+    website.configuration.retry_empty_body = true;
+    website.configuration.max_retries = 2;
+    website.scrape().unwrap();
+
+    assert_eq!(website.get_pages()[0].get_html(), "<p>content</p>");
+}
+
+#[test]
+fn crawl_resume_from_files() {
+    use std::sync::Mutex;
+
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let requested = requests.clone();
+    let address = mock_server(move |request| {
+        requested.lock().unwrap().push(request.lines().next().unwrap_or_default().to_string());
+        mock_html(r#"<a href="/a">a</a><a href="/b">b</a>"#)
+    });
+    let frontier_path = std::env::temp_dir().join("spider_resume_frontier.txt");
+    let visited_path = std::env::temp_dir().join("spider_resume_visited.txt");
+    fs::write(&frontier_path, format!("{}/a\n{}/b\n", address, address)).unwrap();
+    fs::write(&visited_path, format!("{}/\n{}/a\n", address, address)).unwrap();
+
+    let mut website: Website = Website::new(&address);
+    website.configuration.visited_file = Some(visited_path.clone());
+    website.resume_from_files(&frontier_path, &visited_path).unwrap();
+    website.crawl().unwrap();
+
+    assert_eq!(*requests.lock().unwrap(), vec!["GET /b HTTP/1.1".to_string()]);
+    assert!(fs::read_to_string(&visited_path).unwrap().lines().any(|l| l.ends_with("/b")));
+
+    fs::remove_file(&frontier_path).ok();
+    fs::remove_file(&visited_path).ok();
+}
+
+#[test]
+fn crawl_frontier_empty_callback() {
+    use std::sync::Mutex;
+
+    let address = mock_server(|_| mock_html(r#"<a href="/a">a</a>"#));
+    let reason = Arc::new(Mutex::new(None));
+    let reason_found = reason.clone();
+    let mut website: Website = Website::new(&address);
+    website.on_frontier_empty = Some(Box::new(move |r| {
+        *reason_found.lock().unwrap() = Some(r.to_string());
+    }));
+    website.crawl().unwrap();
+
+    assert_eq!(*reason.lock().unwrap(), Some("frontier exhausted".to_string()));
+}
+
+#[test]
+fn scrape_ndjson() {
+    let address = mock_server(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<html><head><title>Home</title></head><body><a href="/a">a</a></body></html>"#
+        } else {
+            "<html><body>a</body></html>"
+        };
+        mock_html(body)
+    });
+    let mut output: Vec<u8> = Vec::new();
+    let mut website: Website = Website::new(&address);
+    website.scrape_ndjson(&mut output).unwrap();
+    let lines: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+
+    assert_eq!(lines.len(), 2);
+    assert!(website.pages.is_empty());
+    for line in lines {
+        assert_eq!(line["status"], 200);
+        if line["url"] == format!("{}/", address) {
+            assert_eq!(line["title"], "Home");
+            assert_eq!(line["links"], 1);
+        } else {
+            assert_eq!(line["title"], serde_json::Value::Null);
+            assert_eq!(line["links"], 0);
+        }
+    }
+}
+
+#[test]
+fn crawl_subsequential() {
+    let mut website: Website = Website::new("https://choosealicense.com");
+    website.configuration.delay = 250;
+    website.crawl_sync().unwrap();
+    assert!(
+        website
+            .links_visited
+            .contains(&"https://choosealicense.com/licenses".to_string()),
+        "{:?}",
+        website.links_visited
+    );
+}
+
+#[test]
+fn crawl_invalid() {
+    let url = "https://w.com";
+    let mut website: Website = Website::new(url);
+    website.crawl().unwrap();
+    let mut uniq = HashSet::new();
+    uniq.insert(Url::parse("https://w.com/").unwrap()); // the root is reported with the `/` path
+
+    assert_eq!(website.links_visited, uniq); // only the target url should exist
+}
+
+#[test]
+fn root_trailing_slash() {
+    let website: Website = Website::new("https://w.com");
+    assert_eq!(website.domain.as_str(), "https://w.com/");
+    assert_eq!(website.links, vec![Url::parse("https://w.com/").unwrap()].into_iter().collect());
+
+    let page = Page::build(&website.domain, r#"<a href="https://w.com">root</a><a href="/">root</a>"#);
+    assert_eq!(page.links(), website.links);
+
+    let website: Website = Website::new("https://w.com/docs");
+    assert_eq!(website.domain.as_str(), "https://w.com/docs");
+}
+
+#[test]
+fn crawl_trailing_slash() {
+    let (_, mut website) = mock_website(|request| {
+        if request.starts_with("GET / ") {
+            mock_html(r#"<a href="/page">page</a><a href="/page/">page</a><a href="/docs">docs</a>"#)
+        } else if request.starts_with("GET /docs ") {
+            mock_response("301 Moved Permanently", "Location: /docs/\r\n", "")
+        } else if request.starts_with("GET /docs/ ") {
+            mock_html(r#"<link rel="next" href="guide">"#)
+        } else {
+            mock_html(r#"<a href="/page">page</a>"#)
+        }
+    });
+    website.configuration.include_link_elements = true;
+    website.crawl().unwrap();
+
+    let mut visited: Vec<&str> = website.links_visited.iter().map(|l| l.path()).collect();
+    visited.sort();
+    assert_eq!(visited, vec!["/", "/docs", "/docs/guide", "/page"]);
+}
+
+#[test]
+fn crawl_link_callback() {
+    let mut website: Website = Website::new("https://choosealicense.com");
+    website.on_link_find_callback = Arc::new(|s| {
+       log("callback link target: {}", &s);
+        s
+    });
+    website.crawl().unwrap();
+    assert!(
+        website
+            .links_visited
+            .contains(&"https://choosealicense.com/licenses".to_string()),
+        "{:?}",
+        website.links_visited
+    );
+}
+
+#[test]
+fn crawl_link_callback_state() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let address = mock_server(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a><a href="/b">b</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    let seen = Arc::new(AtomicUsize::new(0));
+    let counter = seen.clone();
+    let mut website: Website = Website::new(&address);
+    website.configuration.delay = 0;
+    website.on_link_find_callback = Arc::new(move |link| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        link
+    });
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 3);
+    assert_eq!(seen.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn scrape_page_callback() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let address = mock_server(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a><a href="/b">b</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let mut website: Website = Website::new(&address);
+    website.configuration.delay = 0;
+    website.on_page_callback = Some(Box::new(move |_page| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    }));
+    website.scrape().unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), website.links_visited.len());
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn scrape_output_sink() {
+    use std::sync::Mutex;
+
+    /// sink counting the pages written and the calls to finish.
+    struct CountingSink(Arc<Mutex<(Vec<String>, usize)>>);
+
+    impl OutputSink for CountingSink {
+        fn write_page(&mut self, page: &Page) {
+            self.0.lock().unwrap().0.push(page.get_url().to_string());
+        }
+        fn finish(&mut self) {
+            self.0.lock().unwrap().1 += 1;
+        }
+    }
+
+    let address = mock_server(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a><a href="/b">b</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    let counts = Arc::new(Mutex::new((Vec::new(), 0)));
+    let mut website: Website = Website::new(&address);
+    website.configuration.delay = 0;
+    website.add_sink(Box::new(CountingSink(counts.clone())));
+    website.scrape().unwrap();
+    let (pages, finished) = counts.lock().unwrap().clone();
+    let pages: HashSet<String> = pages.into_iter().collect();
+
+    assert_eq!(pages, website.get_pages().iter().map(|page| page.get_url().to_string()).collect());
+    assert_eq!(pages.len(), 3);
+    assert_eq!(finished, 1);
+}
+
+#[test]
+fn check_page_links() {
+    let (address, mut website) = mock_website(|request| {
+        if request.starts_with("HEAD /missing ") || request.starts_with("GET /missing ") {
+            mock_response("404 Not Found", "", "")
+        } else if request.starts_with("GET / ") {
+            mock_html(r#"<a href="/ok">ok</a><a href="/missing">missing</a>"#)
+        } else {
+            mock_html("")
+        }
+    });
+    let mut statuses = website.check_page(&format!("{}/", address));
+    statuses.sort_by(|a, b| a.0.cmp(&b.0));
+
+    assert_eq!(
+        statuses,
+        vec![
+            (Url::parse(&format!("{}/missing", address)).unwrap(), StatusCode::NOT_FOUND),
+            (Url::parse(&format!("{}/ok", address)).unwrap(), StatusCode::OK),
+        ]
+    );
+    assert!(website.links_visited.is_empty());
+}
+
+#[test]
+fn scrape_streaming_cancel() {
+    use std::sync::atomic::AtomicUsize;
+
+    lazy_static! {
+        static ref REQUESTS: AtomicUsize = AtomicUsize::new(0);
+    }
+    let (address, mut website) = mock_website(|request| {
+        REQUESTS.fetch_add(1, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(20));
+        let page: usize = request
+            .trim_start_matches("GET /")
+            .split(' ')
+            .next()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(0);
+        let body = format!(r#"<a href="/{}">next</a>"#, page + 1);
+        mock_html(&body)
+    });
+    website.configuration.max_pages = Some(100);
+    let pages = website.scrape_streaming();
+
+    let first: Vec<Page> = pages.iter().take(3).collect();
+    drop(pages);
+    std::thread::sleep(Duration::from_millis(200));
+    let requests = REQUESTS.load(Ordering::SeqCst);
+    std::thread::sleep(Duration::from_millis(200));
+
+    assert_eq!(first.len(), 3);
+    assert_eq!(first[2].get_url(), &format!("{}/2", address));
+    assert!(requests < 10, "{}", requests);
+    assert_eq!(REQUESTS.load(Ordering::SeqCst), requests);
+}
+
+#[test]
+fn crawl_edge_stream() {
+    let (address, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a>"#
+        } else {
+            std::thread::sleep(Duration::from_millis(200));
+            ""
+        };
+        mock_html(body)
+    });
+    let edges = website.edge_stream();
+    let crawled = Arc::new(AtomicBool::new(false));
+    let consumer = {
+        let crawled = crawled.clone();
+        std::thread::spawn(move || {
+            let edge = edges.recv().unwrap();
+            (edge, crawled.load(Ordering::SeqCst))
+        })
+    };
+    website.crawl().unwrap();
+    crawled.store(true, Ordering::SeqCst);
+    let ((source, target), crawled_before_edge) = consumer.join().unwrap();
+
+    assert!(!crawled_before_edge);
+    assert_eq!(source, Url::parse(&address).unwrap());
+    assert_eq!(target, Url::parse(&format!("{}/a", address)).unwrap());
+}
+
+#[test]
+fn crawl_capture_sample() {
+    let (_, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a><a href="/b">b</a>"#
+        } else {
+            "<p>page</p>"
+        };
+        mock_html(body)
+    });
+    website.configuration.capture_sample = Some(1.0);
+    website.crawl().unwrap();
+
+    assert_eq!(website.captures().len(), website.links_visited.len());
+    for capture in website.captures() {
+        assert_eq!(capture.status, Some(StatusCode::OK));
+        assert_eq!(capture.request_headers.get(header::USER_AGENT).unwrap(), website.configuration.user_agent.as_str());
+        assert_eq!(capture.response_headers.get(header::CONTENT_TYPE).unwrap(), "text/html");
+        assert!(!capture.body.is_empty());
+    }
+}
+
+#[test]
+fn crawl_error_summary() {
+    let mut website: Website = Website::new("http://127.0.0.1:1");
+    website.crawl().unwrap();
+    let summary = website.error_summary();
+
+    assert_eq!(summary.len(), 1, "{:?}", summary);
+    assert_eq!(summary.values().sum::<usize>(), 1);
+    assert!(summary.keys().all(|error| error.to_lowercase().contains("connection refused")), "{:?}", summary);
+
+    let (_, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") { r#"<a href="/a">a</a>"# } else { "" };
+        mock_html(body)
+    });
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 2);
+
+    assert!(website.error_summary().is_empty(), "{:?}", website.error_summary());
+}
+
+#[test]
+fn crawl_min_tls_version() {
+    let crawl = |address: &str| {
+        let mut website: Website = Website::new(address);
+        website.configuration.delay = 0;
+        website.configuration.accept_invalid_certs = true;
+        website.configuration.min_tls_version = Some(reqwest::tls::Version::TLS_1_3);
+        website.crawl().unwrap();
+        website.error_summary()
+    };
+
+    let address = mock_tls_server(&[&rustls::version::TLS12], |_| mock_html(""));
+    assert_eq!(crawl(&address).values().sum::<usize>(), 1);
+
+    let address = mock_tls_server(&[&rustls::version::TLS13], |_| mock_html(""));
+    let errors = crawl(&address);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn crawl_dedup_key() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let count = requests.clone();
+    let (_, mut website) = mock_website(move |request| {
+        if request.starts_with("GET /?a=1&b=2 ") || request.starts_with("GET /?b=2&a=1 ") {
+            count.fetch_add(1, Ordering::SeqCst);
+        }
+        mock_html(r#"<a href="/?a=1&b=2">first</a><a href="/?b=2&a=1">second</a>"#)
+    });
+    website.set_dedup_key(Box::new(|url| {
+        let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        pairs.sort();
+        let mut key = url.clone();
+        key.set_query(None);
+        format!("{}{:?}", key, pairs)
+    }));
+    website.crawl().unwrap();
+
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn crawl_host_header() {
+    let (_, mut website) = mock_website(|request| {
+        if request.to_lowercase().contains("host: choosealicense.com\r\n") {
+            mock_html("<html>backend</html>")
+        } else {
+            mock_response("400 Bad Request", "", "")
+        }
+    });
+    website.configuration.host_header = Some("choosealicense.com".into());
+    website.scrape().unwrap();
+
+    assert_eq!(website.get_pages()[0].get_html(), "<html>backend</html>");
+}
+
+#[test]
+fn crawl_custom_headers() {
+    let (_, mut website) = mock_website(|request| {
+        let request = request.to_lowercase();
+        if request.contains("accept-language: de-ch\r\n")
+            && request.contains("connection: close\r\n")
+            && request.contains("user-agent: auditbot\r\n")
+        {
+            mock_html("<html>custom</html>")
+        } else {
+            mock_response("400 Bad Request", "", "")
+        }
+    });
+    website.configuration.headers.insert(header::ACCEPT_LANGUAGE, header::HeaderValue::from_static("de-CH"));
+    website.configuration.headers.insert(CONNECTION, header::HeaderValue::from_static("close"));
+    website.configuration.headers.insert(header::USER_AGENT, header::HeaderValue::from_static("auditbot"));
+    website.scrape().unwrap();
+
+    assert_eq!(website.get_pages()[0].get_html(), "<html>custom</html>");
+}
+
+#[test]
+fn crawl_orphans() {
+    let (address, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/linked">linked</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.crawl().unwrap();
+
+    let page = |path: &str| Url::parse(&format!("{}{}", address, path)).unwrap();
+    let sitemap_urls: HashSet<Url> = ["/", "/linked", "/orphan"].iter().map(|path| page(path)).collect();
+
+    assert_eq!(website.orphans(&sitemap_urls), [page("/orphan")].iter().cloned().collect());
+}
+
+#[test]
+fn crawl_proxy() {
+    let proxy = mock_server(|request| {
+        // the proxy receives the absolute URL of the page and the proxy credentials
+        if request.starts_with("GET http://proxied.invalid/ ")
+            && request.to_lowercase().contains("proxy-authorization: basic dxnlcjpzzwnyzxq=\r\n")
+        {
+            mock_html("<html>proxied</html>")
+        } else {
+            mock_response("407 Proxy Authentication Required", "", "")
+        }
+    });
+    let mut website: Website = Website::new("http://proxied.invalid");
+    website.configuration.proxy = Some(proxy);
+    website.configuration.proxy_auth = Some(("user".into(), "secret".into()));
+    website.scrape().unwrap();
+
+    assert_eq!(website.get_pages()[0].get_html(), "<html>proxied</html>");
+}
+
+#[test]
+fn crawl_invalid_proxy() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let (_, mut website) = mock_website(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        mock_html("<html>ok</html>")
+    });
+    website.configuration.proxy = Some("not a proxy url".into());
+    let error = website.crawl().unwrap_err();
+
+    assert_eq!(error.kind, CrawlErrorKind::InvalidProxy);
+    assert!(error.error.unwrap().starts_with("invalid proxy"));
+    assert!(website.links_visited.is_empty());
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn crawl_mixed_content() {
+    let resources = mock_server(|_| mock_response("200 OK", "Content-Type: image/png\r\n", ""));
+    let logo = Url::parse(&format!("{}/logo.png", resources)).unwrap();
+    let body = format!(r#"<img src="{}"><a href="/about">about</a>"#, logo);
+    let address = mock_tls_server(&[&rustls::version::TLS13], move |request| {
+        if request.starts_with("GET / ") {
+            mock_html(&body)
+        } else {
+            mock_html(r#"<img src="/logo.png">"#)
+        }
+    });
+    let website = |detect_mixed_content: bool| {
+        let mut website: Website = Website::new(&address);
+        website.configuration.delay = 0;
+        website.configuration.accept_invalid_certs = true;
+        website.configuration.detect_mixed_content = detect_mixed_content;
+        website.crawl().unwrap();
+        website
+    };
+
+    let crawled = website(false);
+    assert_eq!(crawled.links_visited.len(), 2, "{:?}", crawled.links_visited);
+    assert!(crawled.mixed_content().is_empty());
+
+    let crawled = website(true);
+    assert_eq!(crawled.links_visited.len(), 2, "{:?}", crawled.links_visited);
+    assert_eq!(crawled.mixed_content(), vec![(Url::parse(&format!("{}/", address)).unwrap(), logo)]);
+}
+
+#[test]
+fn crawl_enable_cookies() {
+    let address = mock_server(|request| {
+        if request.starts_with("GET / ") {
+            mock_response(
+                "200 OK",
+                "Content-Type: text/html\r\nSet-Cookie: session=abc; Path=/\r\n",
+                r#"<a href="/private">private</a>"#,
+            )
+        } else if request.to_lowercase().contains("cookie: session=abc\r\n") {
+            mock_html("<html>private</html>")
+        } else {
+            mock_response("403 Forbidden", "Content-Type: text/html\r\n", "")
+        }
+    });
+    let private = Url::parse(&format!("{}/private", address)).unwrap();
+
+    let mut website: Website = Website::new(&address);
+    website.configuration.concurrency = 1;
+    website.scrape().unwrap();
+    let page = website.get_pages().into_iter().find(|p| p.get_url() == private.as_str()).unwrap();
+    assert_eq!(page.get_status_code(), Some(StatusCode::FORBIDDEN));
+
+    let mut website: Website = Website::new(&address);
+    website.configuration.concurrency = 1;
+    website.configuration.enable_cookies = true;
+    website.scrape().unwrap();
+    let page = website.get_pages().into_iter().find(|p| p.get_url() == private.as_str()).unwrap();
+    assert_eq!(page.get_html(), "<html>private</html>");
+}
+
+#[test]
+fn crawl_filters() {
+    // the mock serves as proxy so the links can be on subdomains
+    let proxy = mock_server(|request| {
+        let body = if request.starts_with("GET http://example.com/ ") {
+            r#"<a href="/docs/a">a</a><a href="/blog/b">b</a><a href="http://blog.example.com/docs/c">c</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    let mut website: Website = Website::new("http://example.com");
+    website.configuration.delay = 0;
+    website.configuration.proxy = Some(proxy);
+    website.configuration.follow_links = FollowLinks::SUBDOMAINS;
+    website.add_filter(Box::new(|url| url.host_str() == Some("example.com")));
+    website.add_filter(Box::new(|url| url.path() == "/" || url.path().starts_with("/docs/")));
+    website.crawl().unwrap();
+
+    let mut visited: Vec<String> = website.links_visited.iter().map(|l| l.to_string()).collect();
+    visited.sort();
+    assert_eq!(visited, vec!["http://example.com/", "http://example.com/docs/a"]);
+}
+
+#[test]
+fn crawl_canonical_links() {
+    // the mock serves as proxy so the links can use the default port of the scheme
+    let proxy = mock_server(|request| {
+        let body = if request.starts_with("GET http://example.com/ ") {
+            r#"<a href="http://Example.COM:80/x">a</a><a href="http://example.com/x">b</a><a href="http://example.com/x?">c</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    let mut website: Website = Website::new("http://example.com");
+    website.configuration.delay = 0;
+    website.configuration.proxy = Some(proxy);
+    website.crawl().unwrap();
+
+    let mut visited: Vec<String> = website.links_visited.iter().map(|l| l.to_string()).collect();
+    visited.sort();
+    assert_eq!(visited, vec!["http://example.com/", "http://example.com/x"]);
+}
+
+#[test]
+fn crawl_sort_query_params() {
+    let (address, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/p?b=2&a=1">first</a><a href="/p?a=1&b=2">second</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.configuration.sort_query_params = true;
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 2, "{:?}", website.links_visited);
+    assert!(website
+        .links_visited
+        .contains(&Url::parse(&format!("{}/p?a=1&b=2", address)).unwrap()));
+}
+
+#[test]
+fn crawl_strip_query_params() {
+    let (address, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/p?utm_source=a">a</a><a href="/p?utm_medium=b&fbclid=c">b</a><a href="/p?gclid=d">c</a><a href="/p">d</a><a href="/q?id=1&utm_campaign=e">e</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 3, "{:?}", website.links_visited);
+    assert!(website.links_visited.contains(&Url::parse(&format!("{}/p", address)).unwrap()));
+    assert!(website.links_visited.contains(&Url::parse(&format!("{}/q?id=1", address)).unwrap()));
+}
+
+#[test]
+fn crawl_tls_info() {
+    let address = mock_tls_server(&[&rustls::version::TLS13], |_| mock_html(""));
+    let mut website: Website = Website::new(&address);
+    website.configuration.delay = 0;
+    website.configuration.accept_invalid_certs = true;
+    website.configuration.tls_info = true;
+    website.crawl().unwrap();
+    let tls_info = website.tls_info("localhost").expect("no tls info captured");
+
+    assert!(!tls_info.peer_certificate.is_empty());
+    assert!(tls_info.not_after.is_some_and(|not_after| not_after > std::time::SystemTime::now()));
+    assert_eq!(tls_info.protocol.as_deref(), Some("TLSv1.3"));
+    assert!(tls_info.cipher.is_some_and(|cipher| cipher.starts_with("TLS13_")));
+}
+
+#[test]
+fn crawl_case_insensitive_paths() {
+    let (address, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/Page?Q=A">upper</a><a href="/page?Q=A">lower</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.configuration.case_insensitive_paths = true;
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 2, "{:?}", website.links_visited);
+    assert!(website
+        .links_visited
+        .contains(&Url::parse(&format!("{}/page?Q=A", address)).unwrap()));
+}
+
+#[test]
+fn crawl_canonicalize_mobile() {
+    let mut website: Website = Website::new("https://example.com");
+    website.configuration.follow_links = FollowLinks::SUBDOMAINS;
+    website.configuration.canonicalize_mobile = true;
+    let page = Page::build(&website.domain, r#"<a href="https://m.example.com/about">mobile</a>"#);
+    let mut new_links = HashSet::new();
+    let links = page.links_with_configuration(&website.configuration).into_iter().map(|l| website.normalize(l)).collect();
+    website.discover(&page, links, &mut new_links);
+
+    assert_eq!(new_links, vec![Url::parse("https://example.com/about").unwrap()].into_iter().collect());
+}
+
+#[test]
+fn crawl_revisit_after() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let count = requests.clone();
+    let (_, mut website) = mock_website(move |request| {
+        if request.starts_with("GET /page ") {
+            count.fetch_add(1, Ordering::SeqCst);
+        }
+        mock_html(r#"<a href="/page">page</a>"#)
+    });
+    website.configuration.revisit_after = Some(Duration::from_millis(500));
+    website.crawl().unwrap();
+    website.crawl().unwrap();
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    std::thread::sleep(Duration::from_millis(600));
+    website.crawl().unwrap();
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn crawl_branching_factor() {
+    let (_, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a><a href="/b">b</a>"#
+        } else if request.starts_with("GET /a ") {
+            r#"<a href="/b">b</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 3);
+    assert!((website.branching_factor() - 1.0).abs() < f64::EPSILON);
+}
+
+#[test]
+fn crawl_pagination_attrs() {
+    let address = mock_server(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<button data-next-url="/list?page=2">Load more</button>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    let next = Url::parse(&format!("{}/list?page=2", address)).unwrap();
+
+    let mut website: Website = Website::new(&address);
+    website.crawl().unwrap();
+    assert!(!website.links_visited.contains(&next));
+
+    let mut website: Website = Website::new(&address);
+    website.configuration.pagination_attrs.push("data-next-url".into());
+    website.crawl().unwrap();
+    assert!(website.links_visited.contains(&next), "{:?}", website.links_visited);
+}
+
+#[test]
+fn crawl_local() {
+    let root = std::env::temp_dir().join("spider_crawl_local");
+    fs::create_dir_all(root.join("sub")).unwrap();
+    fs::write(root.join("index.html"), r#"<a href="a.html">a</a><a href="sub/b.html">b</a>"#).unwrap();
+    fs::write(root.join("a.html"), r#"<a href="index.html">home</a>"#).unwrap();
+    fs::write(root.join("sub/b.html"), r#"<a href="../a.html">a</a><a href="../../outside.html">out</a>"#).unwrap();
+
+    let mut website: Website = Website::new_local(&root);
+    website.crawl().unwrap();
+    let base = Url::from_directory_path(fs::canonicalize(&root).unwrap()).unwrap();
+
+    assert!(website.links_visited.contains(&base.join("a.html").unwrap()), "{:?}", website.links_visited);
+    assert!(website.links_visited.contains(&base.join("sub/b.html").unwrap()), "{:?}", website.links_visited);
+    assert!(!website.links_visited.iter().any(|l| l.path().ends_with("outside.html")));
+    assert!(website.error_summary().is_empty(), "{:?}", website.error_summary());
+
+    fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn crawl_unicode_normalize() {
+    let (address, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/caf%C3%A9">nfc</a><a href="/cafe%CC%81">nfd</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.configuration.unicode_normalize = true;
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 2, "{:?}", website.links_visited);
+    assert!(website
+        .links_visited
+        .contains(&Url::parse(&format!("{}/caf%C3%A9", address)).unwrap()));
+}
+
+#[test]
+fn crawl_discovery_window() {
+    let (address, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a>"#
+        } else if request.starts_with("GET /a ") {
+            std::thread::sleep(Duration::from_secs(3));
+            r#"<a href="/b">b</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.configuration.discovery_window = Some(Duration::from_secs(1));
+    website.crawl().unwrap();
+
+    assert!(website.links_visited.contains(&Url::parse(&format!("{}/a", address)).unwrap()));
+    assert_eq!(website.links_visited.len(), 2, "{:?}", website.links_visited);
+}
+
+#[test]
+fn crawl_prefetch_robots() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let second_robots = Arc::new(AtomicUsize::new(0));
+    let second_robots_count = second_robots.clone();
+    let second = mock_server(move |request| {
+        let body = if request.starts_with("GET /robots.txt ") {
+            second_robots_count.fetch_add(1, Ordering::SeqCst);
+            "User-agent: *\nDisallow: /secret"
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    let first_robots = Arc::new(AtomicUsize::new(0));
+    let first_robots_count = first_robots.clone();
+    let second_address = second.clone();
+    let first = mock_server(move |request| {
+        let body = if request.starts_with("GET /robots.txt ") {
+            first_robots_count.fetch_add(1, Ordering::SeqCst);
+            "User-agent: *\nDisallow: /private".to_string()
+        } else if request.starts_with("GET / ") {
+            format!(
+                r#"<a href="/private">p</a><a href="/public">p</a><a href="{}/secret">s</a>"#,
+                second_address
+            )
+        } else {
+            String::new()
+        };
+        mock_html(&body)
+    });
+    let mut website: Website = Website::new(&first);
+    website.configuration.delay = 0;
+    website.configuration.respect_robots_txt = true;
+    website.configuration.follow_links = FollowLinks::ALL;
+    website.prefetch_robots(&[&first, second.trim_start_matches("http://")]);
+
+    assert_eq!(website.robots_parsers.len(), 2);
+    assert_eq!(first_robots.load(Ordering::SeqCst), 1);
+    assert_eq!(second_robots.load(Ordering::SeqCst), 1);
+
+    website.crawl().unwrap();
+
+    assert_eq!(first_robots.load(Ordering::SeqCst), 1);
+    assert_eq!(second_robots.load(Ordering::SeqCst), 1);
+    let visited = |link: String| website.links_visited.contains(&Url::parse(&link).unwrap());
+    assert!(visited(format!("{}/public", first)));
+    assert!(!visited(format!("{}/private", first)));
+    assert!(website.is_allowed_robots(&Url::parse(&format!("{}/open", second)).unwrap()));
+    assert!(!website.is_allowed_robots(&Url::parse(&format!("{}/secret", second)).unwrap()));
+}
+
+#[test]
+fn crawl_fail_fast() {
+    let (address, mut website) = mock_website(|request| {
+        if request.starts_with("GET /dead ") {
+            mock_response("404 Not Found", "", "")
+        } else {
+            mock_html(r#"<a href="/dead">dead</a>"#)
+        }
+    });
+    website.configuration.fail_fast = true;
+    let error = website.crawl().unwrap_err();
+
+    assert_eq!(error.url, format!("{}/dead", address));
+    assert_eq!(error.status, Some(StatusCode::NOT_FOUND));
+}
+
+#[test]
+fn crawl_max_fanout() {
+    let (_, mut website) = mock_website(|request| {
+        let path = request.split_whitespace().nth(1).unwrap_or("/").trim_end_matches('/').to_string();
+        let body: String = (0..20).map(|i| format!(r#"<a href="{}/{}">link</a>"#, path, i)).collect();
+        mock_html(&body)
+    });
+    website.configuration.max_fanout = Some(5.0);
+    let error = website.crawl().unwrap_err();
+
+    assert!(error.error.unwrap().starts_with("fanout exceeded"));
+    assert!(website.links_visited.len() <= 21, "{}", website.links_visited.len());
+}
+
+#[test]
+fn crawl_page_timeout() {
+    let (_, mut website) = mock_website(|request| {
+        if request.starts_with("GET /slow ") {
+            std::thread::sleep(Duration::from_secs(3));
+        }
+        mock_html(r#"<a href="/slow">slow</a>"#)
+    });
+    website.configuration.page_timeout = Some(Duration::from_millis(300));
+    let start = Instant::now();
+    website.crawl().unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(website.error_summary().values().sum::<usize>(), 1);
+}
+
+#[test]
+fn crawl_expand_numbered_pagination() {
+    let (address, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET /list?page=1 ") || request.starts_with("GET / ") {
+            r#"<a href="/list?page=1">1</a><a href="/list?page=2">2</a><a href="/list?page=5">5</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.configuration.expand_numbered_pagination = true;
+    website.crawl().unwrap();
+
+    for number in 1..=5 {
+        let page = Url::parse(&format!("{}/list?page={}", address, number)).unwrap();
+        assert!(website.links_visited.contains(&page), "{:?}", website.links_visited);
+    }
+    assert_eq!(website.links_visited.len(), 6);
+}
+
+#[test]
+fn crawl_max_depth() {
+    let (address, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a><a href="/b">b</a>"#
+        } else if request.starts_with("GET /a ") {
+            r#"<a href="/a/c">c</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.configuration.max_depth = Some(1);
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 3, "{:?}", website.links_visited);
+    assert!(!website.links_visited.contains(&Url::parse(&format!("{}/a/c", address)).unwrap()));
+}
+
+#[test]
+fn crawl_max_breadth_per_level() {
+    let (_, mut website) = mock_website(|request| {
+        let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+        let body: String = if path == "/" {
+            (0..10).map(|i| format!(r#"<a href="/p{}">p</a>"#, i)).collect()
+        } else if path.matches('/').count() == 1 {
+            format!(r#"<a href="{0}/a">a</a><a href="{0}/b">b</a>"#, path)
+        } else {
+            String::new()
+        };
+        mock_html(&body)
+    });
+    website.configuration.max_breadth_per_level = Some(3);
+    website.crawl().unwrap();
+    let level = |segments: usize| {
+        website.links_visited.iter().filter(|link| link.path() != "/" && link.path().matches('/').count() == segments).count()
+    };
+
+    assert_eq!(level(1), 3, "{:?}", website.links_visited);
+    assert_eq!(level(2), 3, "{:?}", website.links_visited);
+    assert_eq!(website.links_visited.len(), 7);
+}
+
+#[test]
+fn crawl_on_body() {
+    use std::sync::Mutex;
+
+    let address = mock_server(|_| mock_html("<p>body</p>"));
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let received = bodies.clone();
+    let mut website: Website = Website::new(&address);
+    website.configuration.delay = 0;
+    website.on_body = Some(Box::new(move |url, body| {
+        received.lock().unwrap().push((url.clone(), body.to_vec()));
+    }));
+    website.crawl().unwrap();
+
+    let bodies = bodies.lock().unwrap();
+    assert_eq!(bodies.len(), 1);
+    assert_eq!(bodies[0].0, website.domain);
+    assert_eq!(bodies[0].1, b"<p>body</p>".to_vec());
+}
+
+#[test]
+fn crawl_max_pages() {
+    let (_, mut website) = mock_website(|request| {
+        let links: String = (0..20).map(|i| format!(r#"<a href="/{}">{}</a>"#, i, i)).collect();
+        let body = if request.starts_with("GET / ") { links } else { String::new() };
+        mock_html(&body)
+    });
+    website.configuration.max_pages = Some(5);
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 5);
+}
+
+#[test]
+fn continue_crawl() {
+    let (_, mut website) = mock_website(|request| {
+        let links: String = (0..20).map(|i| format!(r#"<a href="/{}">{}</a>"#, i, i)).collect();
+        let body = if request.starts_with("GET / ") { links } else { String::new() };
+        mock_html(&body)
+    });
+    website.configuration.max_pages = Some(3);
+    website.crawl().unwrap();
+    let first_batch = website.links_visited.clone();
+
+    website.continue_crawl(3).unwrap();
+
+    assert_eq!(first_batch.len(), 3);
+    assert_eq!(website.links_visited.len(), 6);
+    assert!(first_batch.is_subset(&website.links_visited));
+    assert_eq!(website.configuration.max_pages, Some(3));
+}
+
+#[test]
+fn crawl_attachment() {
+    let (address, mut website) = mock_website(|request| {
+        if request.starts_with("GET /report ") {
+            mock_response(
+                "200 OK",
+                "Content-Type: text/html\r\nContent-Disposition: attachment; filename=\"report.html\"\r\n",
+                r#"<a href="/hidden">hidden</a>"#,
+            )
+        } else {
+            mock_html(r#"<a href="/report">report</a>"#)
+        }
+    });
+    website.crawl().unwrap();
+
+    assert!(website.links_visited.contains(&Url::parse(&format!("{}/report", address)).unwrap()));
+    assert!(!website.links_visited.contains(&Url::parse(&format!("{}/hidden", address)).unwrap()));
+}
+
+#[test]
+fn crawl_depth_breadth_ratio() {
+    use std::sync::Mutex;
+
+    let address = mock_server(|request| {
+        let path = request.split(' ').nth(1).unwrap_or_default().to_string();
+        let body = match path.matches('/').count() {
+            1 if path == "/" => (1..=5).map(|i| format!(r#"<a href="/{}">{}</a>"#, i, i)).collect(),
+            1 => format!(r#"<a href="{}/x">x</a>"#, path),
+            2 => format!(r#"<a href="{}/y">y</a>"#, path),
+            _ => String::new(),
+        };
+        mock_html(&body)
+    });
+    // depth of the fourth page visited
+    let fourth_depth = |ratio: f64| {
+        let visits = Arc::new(Mutex::new(Vec::new()));
+        let order = visits.clone();
+        let mut website: Website = Website::new(&address);
+        website.configuration.delay = 0;
+        website.configuration.depth_breadth_ratio = Some(ratio);
+        website.on_body = Some(Box::new(move |url, _| order.lock().unwrap().push(url.clone())));
+        website.crawl_sync().unwrap();
+
+        let visits = visits.lock().unwrap();
+        assert_eq!(visits.len(), 16);
+        visits[3].path_segments().map_or(0, |s| s.filter(|s| !s.is_empty()).count())
+    };
+
+    assert_eq!(fourth_depth(1.0), 3);
+    assert_eq!(fourth_depth(0.0), 1);
+}
+
+#[test]
+fn crawl_request_timeout() {
+    let (_, mut website) = mock_website(|_| {
+        std::thread::sleep(Duration::from_secs(5));
+        mock_html("")
+    });
+    website.configuration.request_timeout = Some(Duration::from_millis(500));
+    let start = Instant::now();
+    website.crawl().unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(website.links_visited.len(), 1);
+    assert_eq!(website.error_summary().values().sum::<usize>(), 1);
 }
 
 #[test]
-fn crawl_invalid() {
-    let url = "https://w.com";
-    let mut website: Website = Website::new(url);
-    website.crawl();
-    let mut uniq = HashSet::new();
-    uniq.insert(format!("{}/", url.to_string())); // TODO: remove trailing slash mutate
+fn crawl_report() {
+    let (address, mut website) = mock_website(|request| {
+        if request.starts_with("GET /dead ") {
+            mock_response("404 Not Found", "", "")
+        } else {
+            mock_html(r#"<a href="/dead">dead</a><a href="/ok">ok</a>"#)
+        }
+    });
+    website.crawl().unwrap();
 
-    assert_eq!(website.links_visited, uniq); // only the target url should exist
+    let report = website.report();
+    assert_eq!(report.stats.visited, 3);
+    assert_eq!(report.broken_links.len(), 1);
+    assert_eq!(report.broken_links[0].url, format!("{}/dead", address));
+    assert_eq!(report.discovered_hosts, vec!["127.0.0.1".to_string()]);
+
+    let json = serde_json::to_value(&report).unwrap();
+    let mut keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec!["broken_links", "discovered_hosts", "pages", "stats", "visited"]);
 }
 
 #[test]
-fn crawl_link_callback() {
-    let mut website: Website = Website::new("https://choosealicense.com");
-    website.on_link_find_callback = |s| {
-       log("callback link target: {}", &s);
-        s
+fn crawl_cache_dir() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let conditional = Arc::new(AtomicUsize::new(0));
+    let count = conditional.clone();
+    let address = mock_server(move |request| {
+        if request.to_lowercase().contains("if-none-match: \"v1\"") {
+            count.fetch_add(1, Ordering::SeqCst);
+            return mock_response("304 Not Modified", "ETag: \"v1\"\r\n", "");
+        }
+        let body = if request.starts_with("GET / ") { r#"<a href="/a">a</a>"# } else { "<p>a</p>" };
+        mock_response("200 OK", "Content-Type: text/html\r\nETag: \"v1\"\r\n", body)
+    });
+    let cache_dir = std::env::temp_dir().join(format!("spider_cache_{}", address.rsplit(':').next().unwrap()));
+    let _ = fs::remove_dir_all(&cache_dir);
+
+    for _ in 0..2 {
+        let mut website: Website = Website::new(&address);
+        website.configuration.delay = 0;
+        website.configuration.cache_dir = Some(cache_dir.clone());
+        website.scrape().unwrap();
+
+        assert_eq!(website.links_visited.len(), 2);
+        assert!(website.get_pages().iter().all(|p| !p.get_html().is_empty()));
+    }
+
+    assert_eq!(conditional.load(Ordering::SeqCst), 2);
+    let _ = fs::remove_dir_all(&cache_dir);
+}
+
+#[test]
+fn crawl_head_precheck() {
+    use std::sync::Mutex;
+
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let log = requests.clone();
+    let (address, mut website) = mock_website(move |request| {
+        let line = request.lines().next().unwrap_or_default().to_string();
+        log.lock().unwrap().push(line.clone());
+        if line.starts_with("HEAD /setup.html ") {
+            mock_response("200 OK", "Content-Type: application/octet-stream\r\n", "")
+        } else if line.starts_with("HEAD ") {
+            mock_response("200 OK", "Content-Type: text/html; charset=utf-8\r\n", "")
+        } else {
+            mock_html(r#"<a href="/setup.html">setup</a>"#)
+        }
+    });
+    website.configuration.head_precheck = true;
+    website.crawl().unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert!(website.links_visited.contains(&Url::parse(&format!("{}/setup.html", address)).unwrap()));
+    assert!(requests.iter().any(|r| r.starts_with("HEAD /setup.html ")));
+    assert!(!requests.iter().any(|r| r.starts_with("GET /setup.html ")));
+    assert!(requests.iter().any(|r| r.starts_with("GET / ")));
+}
+
+#[test]
+fn crawl_allowed_content_types() {
+    let (_, mut website) = mock_website(|request| {
+        if request.starts_with("GET /data ") {
+            mock_response("200 OK", "Content-Type: application/json\r\n", r#"{"html": "<a href=\"/hidden\">h</a>"}"#)
+        } else {
+            mock_response("200 OK", "Content-Type: text/html; charset=utf-8\r\n", r#"<a href="/data">data</a>"#)
+        }
+    });
+    website.configuration.allowed_content_types = Some(vec!["text/html".into(), "application/xhtml+xml".into()]);
+    website.scrape().unwrap();
+
+    assert_eq!(website.links_visited.len(), 2);
+    let data = website.get_pages().into_iter().find(|p| p.get_url().ends_with("/data")).unwrap();
+    assert_eq!(data.get_html(), "");
+    assert!(data.links().is_empty());
+}
+
+#[test]
+fn crawl_async_runtime() {
+    use std::sync::Mutex;
+    use std::thread::ThreadId;
+
+    lazy_static! {
+        static ref THREADS: Mutex<HashSet<ThreadId>> = Mutex::new(HashSet::new());
+    }
+
+    let address = mock_server(|request| {
+        let body = if request.starts_with("GET / ") {
+            (0..16).map(|i| format!(r#"<a href="/{}">{}</a>"#, i, i)).collect()
+        } else {
+            std::thread::sleep(Duration::from_millis(50));
+            String::new()
+        };
+        mock_html(&body)
+    });
+    let crawl = |async_runtime: bool| {
+        THREADS.lock().unwrap().clear();
+        let mut website: Website = Website::new(&address);
+        website.configuration.delay = 0;
+        website.configuration.concurrency = 16;
+        website.configuration.async_runtime = async_runtime;
+        website.on_link_find_callback = Arc::new(|link| {
+            THREADS.lock().unwrap().insert(std::thread::current().id());
+            link
+        });
+        website.crawl().unwrap();
+
+        (website.links_visited.clone(), THREADS.lock().unwrap().len())
     };
-    website.crawl();
-    assert!(
-        website
-            .links_visited
-            .contains(&"https://choosealicense.com/licenses/".to_string()),
-        "{:?}",
-        website.links_visited
+
+    let (visited, threads) = crawl(false);
+    let (visited_async, threads_async) = crawl(true);
+
+    assert_eq!(visited.len(), 17);
+    assert_eq!(visited, visited_async);
+    assert!(threads > 4, "{}", threads);
+    assert_eq!(threads_async, 1);
+}
+
+#[test]
+fn crawl_per_host_delay() {
+    use std::sync::Mutex;
+
+    let host_server = |crawl_delay: u64, requests: Arc<Mutex<Vec<Instant>>>| {
+        mock_server(move |request| {
+            if request.starts_with("GET /robots.txt ") {
+                return mock_response("200 OK", "", &format!("User-agent: *\nCrawl-delay: {}", crawl_delay));
+            }
+            requests.lock().unwrap().push(Instant::now());
+            mock_html(r#"<a href="/a">a</a>"#)
+        })
+    };
+    let first_requests = Arc::new(Mutex::new(Vec::new()));
+    let second_requests = Arc::new(Mutex::new(Vec::new()));
+    let first = host_server(1, first_requests.clone());
+    let second = host_server(2, second_requests.clone());
+
+    let mut website: Website = Website::new(&first);
+    website.configuration.respect_robots_txt = true;
+    website.configuration.follow_links = FollowLinks::ALL;
+    website.prefetch_robots(&[&first, &second]);
+    website.configuration.delay = 0;
+    website.links.insert(Url::parse(&second).unwrap());
+    website.crawl().unwrap();
+
+    let spacing = |requests: &Arc<Mutex<Vec<Instant>>>| {
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        requests[1].duration_since(requests[0])
+    };
+    let first_spacing = spacing(&first_requests);
+    let second_spacing = spacing(&second_requests);
+    assert!(first_spacing >= Duration::from_millis(950), "{:?}", first_spacing);
+    assert!(first_spacing < Duration::from_millis(1900), "{:?}", first_spacing);
+    assert!(second_spacing >= Duration::from_millis(1950), "{:?}", second_spacing);
+}
+
+#[tokio::test]
+async fn crawl_async() {
+    let (address, mut website) = mock_website(|request| {
+        if request.starts_with("GET /robots.txt ") {
+            return mock_response("200 OK", "", "User-agent: *\nDisallow: /private");
+        }
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a><a href="/b">b</a><a href="/private">p</a>"#
+        } else {
+            ""
+        };
+        mock_html(body)
+    });
+    website.configuration.delay = 10;
+    website.configuration.respect_robots_txt = true;
+    website.crawl_async().await.unwrap();
+
+    assert_eq!(website.links_visited.len(), 3, "{:?}", website.links_visited);
+    assert!(!website.links_visited.contains(&Url::parse(&format!("{}/private", address)).unwrap()));
+}
+
+#[test]
+fn crawl_delay() {
+    let (_, mut website) = mock_website(|request| {
+        let body = if request.starts_with("GET / ") { r#"<a href="/a">a</a>"# } else { r#"<a href="/b">b</a>"# };
+        mock_html(body)
+    });
+    website.configuration.delay = 100;
+    let start = Instant::now();
+    website.crawl_sync().unwrap();
+
+    assert_eq!(website.links_visited.len(), 3);
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+#[test]
+fn crawl_max_frontier() {
+    let address = mock_server(|request| {
+        let path = request.split(' ').nth(1).unwrap_or_default().trim_end_matches('/').to_string();
+        let body: String = if path.matches('/').count() < 2 {
+            (0..20).map(|i| format!(r#"<a href="{}/{}">{}</a>"#, path, i, i)).collect()
+        } else {
+            String::new()
+        };
+        mock_html(&body)
+    });
+    for overflow in vec![FrontierOverflow::DropNewest, FrontierOverflow::DropOldest] {
+        let mut website: Website = Website::new(&address);
+        website.configuration.delay = 0;
+        website.configuration.max_frontier = Some(5);
+        website.configuration.frontier_overflow = overflow;
+        website.crawl().unwrap();
+
+        assert!(website.links.is_empty());
+        assert_eq!(website.links_visited.len(), 11, "{:?}", website.links_visited);
+    }
+}
+
+#[test]
+fn crawl_max_conns_per_ip() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    lazy_static! {
+        static ref RUNNING: AtomicUsize = AtomicUsize::new(0);
+        static ref MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+    }
+    let address = mock_server(|_| {
+        let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+        MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(50));
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+        mock_html("")
+    });
+    let localhost = address.replace("127.0.0.1", "localhost");
+    let mut website: Website = Website::new(&localhost);
+    website.configuration.delay = 0;
+    website.configuration.concurrency = 8;
+    website.configuration.follow_links = FollowLinks::ALL;
+    website.configuration.max_conns_per_ip = Some(1);
+    for path in ["/a", "/b", "/c"].iter() {
+        website.links.insert(Url::parse(&format!("{}{}", localhost, path)).unwrap());
+        website.links.insert(Url::parse(&format!("{}{}", address, path)).unwrap());
+    }
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 7);
+    assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn crawl_max_conns_per_ip_async() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    lazy_static! {
+        static ref RUNNING: AtomicUsize = AtomicUsize::new(0);
+        static ref MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+    }
+    let address = mock_server(|_| {
+        let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+        MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(50));
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+        mock_html("")
+    });
+    let localhost = address.replace("127.0.0.1", "localhost");
+    let mut website: Website = Website::new(&localhost);
+    website.configuration.delay = 0;
+    website.configuration.concurrency = 8;
+    website.configuration.async_runtime = true;
+    website.configuration.follow_links = FollowLinks::ALL;
+    website.configuration.max_conns_per_ip = Some(1);
+    for path in ["/a", "/b", "/c"].iter() {
+        website.links.insert(Url::parse(&format!("{}{}", localhost, path)).unwrap());
+        website.links.insert(Url::parse(&format!("{}{}", address, path)).unwrap());
+    }
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 7);
+    assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn crawl_max_concurrent_per_host() {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Mutex;
+
+    lazy_static! {
+        static ref RUNNING: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+        static ref MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+    }
+    let address = mock_server(|request| {
+        let host = request
+            .lines()
+            .find_map(|line| line.strip_prefix("host: ").or_else(|| line.strip_prefix("Host: ")))
+            .and_then(|host| host.split(':').next())
+            .unwrap_or_default()
+            .to_string();
+        {
+            let mut running = RUNNING.lock().unwrap();
+            let count = running.entry(host.clone()).or_insert(0);
+            *count += 1;
+            MAX_RUNNING.fetch_max(*count, Ordering::SeqCst);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        *RUNNING.lock().unwrap().get_mut(&host).unwrap() -= 1;
+        mock_html("")
+    });
+    let localhost = address.replace("127.0.0.1", "localhost");
+    let mut website: Website = Website::new(&localhost);
+    website.configuration.delay = 0;
+    website.configuration.concurrency = 8;
+    website.configuration.follow_links = FollowLinks::ALL;
+    website.configuration.max_concurrent_per_host = Some(2);
+    for path in ["/a", "/b", "/c", "/d"].iter() {
+        website.links.insert(Url::parse(&format!("{}{}", localhost, path)).unwrap());
+        website.links.insert(Url::parse(&format!("{}{}", address, path)).unwrap());
+    }
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 9);
+    assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn crawl_max_concurrent_per_host_async() {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Mutex;
+
+    lazy_static! {
+        static ref RUNNING: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+        static ref MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+    }
+    let address = mock_server(|request| {
+        let host = request
+            .lines()
+            .find_map(|line| line.strip_prefix("host: ").or_else(|| line.strip_prefix("Host: ")))
+            .and_then(|host| host.split(':').next())
+            .unwrap_or_default()
+            .to_string();
+        {
+            let mut running = RUNNING.lock().unwrap();
+            let count = running.entry(host.clone()).or_insert(0);
+            *count += 1;
+            MAX_RUNNING.fetch_max(*count, Ordering::SeqCst);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        *RUNNING.lock().unwrap().get_mut(&host).unwrap() -= 1;
+        mock_html("")
+    });
+    let localhost = address.replace("127.0.0.1", "localhost");
+    let mut website: Website = Website::new(&localhost);
+    website.configuration.delay = 0;
+    website.configuration.concurrency = 8;
+    website.configuration.async_runtime = true;
+    website.configuration.follow_links = FollowLinks::ALL;
+    website.configuration.max_concurrent_per_host = Some(2);
+    for path in ["/a", "/b", "/c", "/d"].iter() {
+        website.links.insert(Url::parse(&format!("{}{}", localhost, path)).unwrap());
+        website.links.insert(Url::parse(&format!("{}{}", address, path)).unwrap());
+    }
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 9);
+    assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn crawl_requests_per_second() {
+    let (_, mut website) = mock_website(|request| {
+        let links: String = (0..10).map(|i| format!(r#"<a href="/{}">{}</a>"#, i, i)).collect();
+        let body = if request.starts_with("GET / ") { links } else { String::new() };
+        mock_html(&body)
+    });
+    website.configuration.concurrency = 8;
+    website.configuration.requests_per_second = Some(5.0);
+    let start = Instant::now();
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 11);
+    assert!(start.elapsed() >= Duration::from_secs_f64(10.0 / 5.0), "{:?}", start.elapsed());
+}
+
+#[test]
+fn crawl_checkpoint_every() {
+    use std::sync::atomic::AtomicUsize;
+
+    lazy_static! {
+        static ref CHECKPOINTED: AtomicUsize = AtomicUsize::new(0);
+    }
+    let path = std::env::temp_dir().join(format!("spider-checkpoint-{}.json", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let state_path = path.clone();
+    let (_, mut website) = mock_website(move |request| {
+        if let Ok(state) = fs::read_to_string(&state_path) {
+            let state: serde_json::Value = serde_json::from_str(&state).unwrap();
+            CHECKPOINTED.fetch_max(state["visited"].as_array().unwrap().len(), Ordering::SeqCst);
+        }
+        // a chain of pages so every round visits one page
+        let page: usize = request
+            .trim_start_matches("GET /")
+            .split(' ')
+            .next()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(0);
+        let body = if page < 11 {
+            format!(r#"<a href="/{}">next</a>"#, page + 1)
+        } else {
+            String::new()
+        };
+        mock_html(&body)
+    });
+    website.configuration.checkpoint_every = Some(CheckpointTrigger::Pages(5));
+    website.configuration.checkpoint_file = Some(path.clone());
+    website.crawl().unwrap();
+
+    let state: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(website.links_visited.len(), 12);
+    assert_eq!(CHECKPOINTED.load(Ordering::SeqCst), 10);
+    assert_eq!(state["visited"].as_array().unwrap().len(), 10);
+    assert_eq!(state["frontier"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn crawl_near_dup_threshold() {
+    let (_, mut website) = mock_website(|request| {
+        let words: Vec<String> = (0..300).map(|i| format!("word{}", i)).collect();
+        let body = if request.starts_with("GET / ") {
+            r#"<a href="/a">a</a><a href="/b">b</a>"#.to_string()
+        } else if request.starts_with("GET /a ") {
+            format!(r#"<p>{} updated 10:00</p><a href="/c">c</a>"#, words.join(" "))
+        } else if request.starts_with("GET /b ") {
+            format!(r#"<p>{} updated 10:01</p><a href="/d">d</a>"#, words.join(" "))
+        } else {
+            String::new()
+        };
+        mock_html(&body)
+    });
+    website.configuration.near_dup_threshold = Some(6);
+    website.crawl().unwrap();
+
+    let followed: Vec<&Url> = website
+        .links_visited
+        .iter()
+        .filter(|l| l.path() == "/c" || l.path() == "/d")
+        .collect();
+    assert_eq!(website.links_visited.len(), 4, "{:?}", website.links_visited);
+    assert_eq!(followed.len(), 1);
+}
+
+#[test]
+fn crawl_urls_by_status() {
+    let (address, mut website) = mock_website(|request| {
+        if request.starts_with("GET / ") {
+            mock_html(r#"<a href="/a">a</a><a href="/b">b</a><a href="/c">c</a>"#)
+        } else if request.starts_with("GET /a ") {
+            mock_html("")
+        } else if request.starts_with("GET /b ") {
+            mock_response("404 Not Found", "", "")
+        } else {
+            mock_response("500 Internal Server Error", "", "")
+        }
+    });
+    website.crawl().unwrap();
+    let url = |path: &str| Url::parse(&format!("{}{}", address, path)).unwrap();
+    let urls = website.urls_by_status();
+
+    assert_eq!(urls.len(), 3);
+    assert_eq!(urls[&StatusCode::OK], vec![url("/"), url("/a")]);
+    assert_eq!(urls[&StatusCode::NOT_FOUND], vec![url("/b")]);
+    assert_eq!(urls[&StatusCode::INTERNAL_SERVER_ERROR], vec![url("/c")]);
+}
+
+/// website whose sitemap index, child sitemaps and robots.txt sitemap list /a, /b, /c and /d.
+#[cfg(test)]
+fn mock_sitemap_website() -> (String, Website<'static>) {
+    let shared = Arc::new(std::sync::Mutex::new(String::new()));
+    let server_address = shared.clone();
+    let (address, mut website) = mock_website(move |request| {
+        let address = server_address.lock().unwrap().clone();
+        let xml = "Content-Type: application/xml\r\n";
+        if request.starts_with("GET /robots.txt ") {
+            mock_response("200 OK", "", &format!("User-agent: *\nSitemap: {}/news.xml\n", address))
+        } else if request.starts_with("GET /sitemap.xml ") {
+            let body = format!(
+                "<sitemapindex><sitemap><loc>{0}/sitemap-a.xml</loc></sitemap><sitemap><loc>{0}/sitemap-b.xml</loc></sitemap></sitemapindex>",
+                address
+            );
+            mock_response("200 OK", xml, &body)
+        } else if request.starts_with("GET /sitemap-a.xml ") {
+            mock_response("200 OK", xml, &format!("<urlset><url><loc>{0}/a</loc></url><url><loc>{0}/b</loc></url></urlset>", address))
+        } else if request.starts_with("GET /sitemap-b.xml ") {
+            mock_response("200 OK", xml, &format!("<urlset><url><loc>{}/c</loc></url></urlset>", address))
+        } else if request.starts_with("GET /news.xml ") {
+            mock_response("200 OK", xml, &format!("<urlset><url><loc>{}/d</loc></url></urlset>", address))
+        } else {
+            mock_html("")
+        }
+    });
+    *shared.lock().unwrap() = address.clone();
+    website.configuration.crawl_sitemap = true;
+
+    (address, website)
+}
+
+#[test]
+fn crawl_sitemap() {
+    let (address, mut website) = mock_sitemap_website();
+    website.crawl().unwrap();
+
+    for path in ["/", "/a", "/b", "/c", "/d"].iter() {
+        assert!(website.links_visited.contains(&Url::parse(&format!("{}{}", address, path)).unwrap()), "{}", path);
+    }
+    assert_eq!(website.links_visited.len(), 5);
+}
+
+#[tokio::test]
+async fn crawl_sitemap_async() {
+    let (address, mut website) = mock_sitemap_website();
+    website.crawl_async().await.unwrap();
+
+    for path in ["/", "/a", "/b", "/c", "/d"].iter() {
+        assert!(website.links_visited.contains(&Url::parse(&format!("{}{}", address, path)).unwrap()), "{}", path);
+    }
+    assert_eq!(website.links_visited.len(), 5);
+}
+
+#[test]
+fn robots_get_sitemaps() {
+    use std::sync::atomic::AtomicUsize;
+
+    lazy_static! {
+        static ref ROBOTS_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+    }
+    let (_, mut website) = mock_website(|request| {
+        if request.starts_with("GET /robots.txt ") {
+            ROBOTS_REQUESTS.fetch_add(1, Ordering::SeqCst);
+            let body = "User-agent: *\nDisallow: /private\nSitemap: https://example.com/sitemap.xml\nSitemap: https://example.com/news.xml.gz\n";
+            mock_response("200 OK", "", body)
+        } else {
+            mock_response("404 Not Found", "", "")
+        }
+    });
+    let expected = vec![
+        Url::parse("https://example.com/sitemap.xml").unwrap(),
+        Url::parse("https://example.com/news.xml.gz").unwrap(),
+    ];
+
+    assert_eq!(website.get_sitemaps(), expected);
+    assert_eq!(website.get_sitemaps(), expected);
+    assert_eq!(ROBOTS_REQUESTS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn robots_user_agent_rules() {
+    let address = mock_server(|request| {
+        if request.starts_with("GET /robots.txt ") {
+            let body = "User-agent: *\nDisallow: /private\n\nUser-agent: examplebot\nDisallow: /secret\n";
+            mock_response("200 OK", "", body)
+        } else {
+            mock_response("404 Not Found", "", "")
+        }
+    });
+    let private = Url::parse(&format!("{}/private", address)).unwrap();
+    let secret = Url::parse(&format!("{}/secret", address)).unwrap();
+
+    let mut website: Website = Website::new(&address);
+    website.configuration.respect_robots_txt = true;
+    website.configuration.user_agent = "examplebot".into();
+    website.configure_robots_parser();
+    assert!(website.is_allowed_robots(&private));
+    assert!(!website.is_allowed_robots(&secret));
+
+    let mut website: Website = Website::new(&address);
+    website.configuration.respect_robots_txt = true;
+    website.configuration.user_agent = "".into();
+    website.configure_robots_parser();
+    assert!(!website.is_allowed_robots(&private));
+    assert!(website.is_allowed_robots(&secret));
+}
+
+#[test]
+fn robots_wildcard_crawl_delay() {
+    let (_, mut website) = mock_website(|request| {
+        if request.starts_with("GET /robots.txt ") {
+            let body = "User-agent: *\nCrawl-delay: 3\n\nUser-agent: examplebot\nDisallow: /secret\n";
+            mock_response("200 OK", "", body)
+        } else {
+            mock_response("404 Not Found", "", "")
+        }
+    });
+    website.configuration.respect_robots_txt = true;
+    website.configuration.user_agent = "examplebot".into();
+    website.configure_robots_parser();
+
+    assert_eq!(website.configuration.delay, 3000);
+}
+
+#[test]
+fn crawl_allowed_redirect_hosts() {
+    lazy_static! {
+        static ref FOLLOWED: AtomicBool = AtomicBool::new(false);
+        static ref PORT: std::sync::Mutex<String> = std::sync::Mutex::new(String::new());
+    }
+    let address = mock_server(|request| {
+        if request.starts_with("GET / ") {
+            mock_html(r#"<a href="/moved">moved</a>"#)
+        } else if request.starts_with("GET /moved ") {
+            let location = format!("Location: http://localhost:{}/elsewhere\r\n", PORT.lock().unwrap());
+            mock_response("301 Moved Permanently", &location, "")
+        } else {
+            FOLLOWED.store(true, Ordering::SeqCst);
+            mock_html("")
+        }
+    });
+    *PORT.lock().unwrap() = address.rsplit(':').next().unwrap().to_string();
+    let mut website: Website = Website::new(&address);
+    website.configuration.delay = 0;
+    website.configuration.allowed_redirect_hosts = Some(vec![]);
+    website.crawl().unwrap();
+    let moved = Url::parse(&format!("{}/moved", address)).unwrap();
+
+    assert!(!FOLLOWED.load(Ordering::SeqCst));
+    assert_eq!(website.report().pages.iter().find(|p| p.url == moved.as_str()).unwrap().status, Some(301));
+    assert_eq!(
+        website.out_of_scope_redirects().get(&moved),
+        Some(&Url::parse(&format!("http://localhost:{}/elsewhere", PORT.lock().unwrap())).unwrap())
+    );
+}
+
+#[test]
+#[cfg(feature = "pdf")]
+fn crawl_parse_pdfs() {
+    use lopdf::xref::{Xref, XrefType};
+    use lopdf::{dictionary, Document, Object};
+
+    lazy_static! {
+        static ref LINKED: AtomicBool = AtomicBool::new(false);
+        static ref PDF: std::sync::Mutex<String> = std::sync::Mutex::new(String::new());
+    }
+    let address = mock_server(|request| {
+        if request.starts_with("GET / ") {
+            mock_html(r#"<a href="/doc.pdf">doc</a>"#)
+        } else if request.starts_with("GET /doc.pdf ") {
+            mock_response("200 OK", "Content-Type: application/pdf\r\n", &PDF.lock().unwrap())
+        } else {
+            LINKED.store(true, Ordering::SeqCst);
+            mock_html("")
+        }
+    });
+    let mut document = Document::with_version("1.4");
+    document.reference_table = Xref::new(0, XrefType::CrossReferenceTable);
+    let action = dictionary! { "S" => "URI", "URI" => Object::string_literal(format!("{}/linked", address)) };
+    let annotation = document.add_object(dictionary! { "Type" => "Annot", "Subtype" => "Link", "A" => action });
+    let pages = document.add_object(dictionary! { "Type" => "Pages", "Kids" => vec![], "Count" => 0 });
+    let catalog = document.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages, "Annots" => vec![annotation.into()] });
+    document.trailer.set("Root", catalog);
+    let mut pdf = Vec::new();
+    document.save_to(&mut pdf).unwrap();
+    *PDF.lock().unwrap() = String::from_utf8(pdf).unwrap();
+
+    let mut website: Website = Website::new(&address);
+    website.configuration.delay = 0;
+    website.crawl().unwrap();
+    assert!(!LINKED.load(Ordering::SeqCst));
+
+    let mut website: Website = Website::new(&address);
+    website.configuration.delay = 0;
+    website.configuration.parse_pdfs = true;
+    website.crawl().unwrap();
+    assert!(LINKED.load(Ordering::SeqCst));
+    assert!(website.get_links().contains(&Url::parse(&format!("{}/doc.pdf", address)).unwrap()));
+}
+
+#[test]
+fn crawl_skips_websocket_links() {
+    let (_, mut website) = mock_website(|_| {
+        let body = r#"<a href="wss://example.com/socket.html">socket</a><div data-next="ws://example.com/live"></div><div data-next="tel://+212 3456"></div>"#;
+        mock_html(body)
+    });
+    website.configuration.follow_links = FollowLinks::ALL;
+    website.configuration.pagination_attrs = vec!["data-next".to_string()];
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 1, "{:?}", website.links_visited);
+    assert_eq!(website.report().pages.len(), 1);
+    assert!(!website.is_allowed(&Url::parse("wss://example.com/socket").unwrap()));
+}
+
+#[test]
+fn crawl_delay_jitter() {
+    use std::sync::Mutex;
+
+    lazy_static! {
+        static ref REQUESTED_AT: Mutex<Vec<Instant>> = Mutex::new(Vec::new());
+    }
+    let (_, mut website) = mock_website(|request| {
+        REQUESTED_AT.lock().unwrap().push(Instant::now());
+        let links: String = (0..5).map(|i| format!(r#"<a href="/{}">{}</a>"#, i, i)).collect();
+        let body = if request.starts_with("GET / ") { links } else { String::new() };
+        mock_html(&body)
+    });
+    website.configuration.delay_jitter = Some((50, 250));
+    website.crawl_sync().unwrap();
+    let requested_at = REQUESTED_AT.lock().unwrap();
+    let gaps: Vec<Duration> = requested_at.windows(2).map(|w| w[1] - w[0]).collect();
+
+    assert_eq!(gaps.len(), 5);
+    assert!(gaps.iter().all(|gap| *gap >= Duration::from_millis(50) && *gap <= Duration::from_millis(350)), "{:?}", gaps);
+    assert!(*gaps.iter().max().unwrap() - *gaps.iter().min().unwrap() >= Duration::from_millis(10), "{:?}", gaps);
+}
+
+#[test]
+fn crawl_follow_none() {
+    let (_, mut website) = mock_website(|_| {
+        mock_html(r#"<a href="/a">a</a><a href="/b">b</a>"#)
+    });
+    website.configuration.follow_links = FollowLinks::NONE;
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited.len(), 1);
+    assert!(website.links_visited.contains(&website.domain));
+}
+
+#[test]
+fn subdomains_scope() {
+    let mut website: Website = Website::new("https://example.com");
+    website.configuration.follow_links = FollowLinks::SUBDOMAINS;
+    let allowed = |link: &str| website.is_allowed(&Url::parse(link).unwrap());
+
+    assert!(allowed("https://example.com/about"));
+    assert!(allowed("https://docs.example.com/x"));
+    assert!(allowed("https://api.docs.example.com:8080/x"));
+    assert!(allowed("https://docs.example.com./x"));
+    assert!(!allowed("https://example.org/"));
+    assert!(!allowed("https://notexample.com/"));
+}
+
+#[test]
+fn same_domain_scope() {
+    let mut website: Website = Website::new("https://blog.example.co.uk");
+    website.configuration.follow_links = FollowLinks::SAMEDOMAIN;
+    let in_scope = |link: &str| website.is_in_scope(&Url::parse(link).unwrap());
+
+    assert!(in_scope("https://shop.example.co.uk/cart"));
+    assert!(in_scope("https://example.co.uk/page"));
+    assert!(in_scope("https://blog.example.co.uk/post"));
+    assert!(!in_scope("https://other.co.uk/"));
+    assert!(!in_scope("https://example.com/"));
+    assert!(!in_scope("https://co.uk/"));
+}
+
+#[test]
+fn crawl_same_domain() {
+    let mut website: Website = Website::new("https://blog.example.co.uk");
+    website.configuration.follow_links = FollowLinks::SAMEDOMAIN;
+    let page = Page::build(
+        &website.domain,
+        r#"<a href="https://shop.example.co.uk/cart">cart</a><a href="https://other.co.uk/">other</a>"#,
     );
+    let links: HashSet<Url> = page
+        .links_with_configuration(&website.configuration)
+        .into_iter()
+        .filter(|l| website.is_allowed(l))
+        .collect();
+
+    assert_eq!(links, vec![Url::parse("https://shop.example.co.uk/cart").unwrap()].into_iter().collect());
 }
 
 #[test]
+#[cfg(not(any(feature = "regex", feature = "glob")))]
 fn not_crawl_blacklist() {
     let mut website: Website = Website::new("https://choosealicense.com");
     website
         .configuration
         .blacklist_url
         .push(Url::parse("https://choosealicense.com/licenses/").unwrap());
-    website.crawl();
+    website.crawl().unwrap();
     assert!(
         !website
             .links_visited
@@ -412,11 +4003,107 @@ fn not_crawl_blacklist_regex() {
     website
         .configuration
         .blacklist_url
-        .push(Url::parse("/choosealicense.com/"));
-    website.crawl();
+        .push("/choosealicense.com/".to_string());
+    website.crawl().unwrap();
     assert_eq!(website.links_visited.len(), 0);
 }
 
+#[test]
+#[cfg(feature = "regex")]
+fn crawl_blacklist_regex_malformed() {
+    let mut website: Website = Website::new("http://127.0.0.1:1");
+    website.configuration.blacklist_url.push("/licenses/(".to_string());
+    let error = website.crawl().unwrap_err();
+
+    assert!(error.error.unwrap().starts_with("invalid url pattern"));
+    assert!(website.links_visited.is_empty());
+}
+
+#[test]
+#[cfg(not(any(feature = "regex", feature = "glob")))]
+fn crawl_whitelist() {
+    let address = mock_server(|request| {
+        if request.starts_with("GET / ") {
+            mock_html(r#"<a href="/a">a</a><a href="/b">b</a><a href="/c">c</a>"#)
+        } else {
+            mock_html("")
+        }
+    });
+    let url = |path: &str| Url::parse(&format!("{}{}", address, path)).unwrap();
+
+    let mut website: Website = Website::new(&address);
+    website.configuration.delay = 0;
+    website.crawl().unwrap();
+    assert_eq!(website.get_links().len(), 4);
+
+    let mut website: Website = Website::new(&address);
+    website.configuration.delay = 0;
+    website.configuration.whitelist_url = vec![url("/"), url("/a"), url("/b")];
+    website.configuration.blacklist_url = vec![url("/b")];
+    website.crawl().unwrap();
+    assert_eq!(website.get_links(), &vec![url("/"), url("/a")].into_iter().collect::<HashSet<Url>>());
+}
+
+#[test]
+#[cfg(not(any(feature = "regex", feature = "glob")))]
+fn crawl_lists_trailing_slash() {
+    let (address, mut website) = mock_website(|request| {
+        if request.starts_with("GET / ") {
+            mock_html(r#"<a href="/a">a</a><a href="/b">b</a>"#)
+        } else {
+            mock_html("")
+        }
+    });
+    let url = |path: &str| Url::parse(&format!("{}{}", address, path)).unwrap();
+    website.configuration.whitelist_url = vec![url("/"), url("/a"), url("/b/")];
+    website.configuration.blacklist_url = vec![url("/a/")];
+    website.crawl().unwrap();
+
+    assert_eq!(website.links_visited, vec![url("/"), url("/b")].into_iter().collect());
+}
+
+#[test]
+#[cfg(all(feature = "glob", not(feature = "regex")))]
+fn crawl_blacklist_glob() {
+    let (address, mut website) = mock_website(|request| {
+        if request.starts_with("GET / ") {
+            let body = r#"<a href="/admin/users">a</a><a href="/docs/private/a">b</a><a href="/page-1.html">c</a><a href="/page-10.html">d</a><a href="/secret">e</a>"#;
+            mock_html(body)
+        } else {
+            mock_html("")
+        }
+    });
+    website.configuration.blacklist_url = vec![
+        format!("{}/admin/*", address),
+        "http://*/private/*".to_string(),
+        "*/page-?.html".to_string(),
+        // a `?` in the host is not the start of a query
+        "http://127.0.0.?:*/secret".to_string(),
+    ];
+    website.crawl().unwrap();
+    let paths: HashSet<&str> = website.links_visited.iter().map(|link| link.path()).collect();
+
+    assert_eq!(paths, vec!["/", "/page-10.html"].into_iter().collect());
+}
+
+#[test]
+#[cfg(all(feature = "glob", not(feature = "regex")))]
+fn crawl_whitelist_glob() {
+    let (address, mut website) = mock_website(|request| {
+        if request.starts_with("GET / ") {
+            mock_html(r#"<a href="/a">a</a><a href="/b">b</a><a href="/docs/c">c</a>"#)
+        } else {
+            mock_html("")
+        }
+    });
+    website.configuration.whitelist_url = vec![format!("{}/", address), "*/?".to_string(), "*/docs/*".to_string()];
+    website.configuration.blacklist_url = vec!["*/b".to_string()];
+    website.crawl().unwrap();
+    let paths: HashSet<&str> = website.links_visited.iter().map(|link| link.path()).collect();
+
+    assert_eq!(paths, vec!["/", "/a", "/docs/c"].into_iter().collect());
+}
+
 #[test]
 fn test_respect_robots_txt() {
     let mut website: Website = Website::new("https://stackoverflow.com");
@@ -435,7 +4122,7 @@ fn test_respect_robots_txt() {
     );
     assert_eq!(website_second.configuration.delay, 60000); // should equal one minute in ms
 
-    // test crawl delay with wildcard agent [DOES not work when using set agent]
+    // test crawl delay with wildcard agent
     let mut website_third: Website = Website::new("https://www.mongodb.com");
     website_third.configuration.respect_robots_txt = true;
     website_third.configure_robots_parser();
@@ -455,7 +4142,7 @@ fn test_link_duplicates() {
     }
 
     let mut website: Website = Website::new("http://0.0.0.0:8000");
-    website.crawl();
+    website.crawl().unwrap();
 
     assert!(has_unique_elements(&website.links_visited));
 }
@@ -46,9 +46,9 @@ fn main() {
     match &cli.command {
         Some(Commands::CRAWL { sync, output_links }) => {
             if *sync {
-                website.crawl_sync();
+                website.crawl_sync().unwrap();
             } else {
-                website.crawl();
+                website.crawl().unwrap();
             }
 
             if *output_links {
@@ -60,7 +60,7 @@ fn main() {
         Some(Commands::SCRAPE { output_html, output_links }) => {
             use serde_json::{json};
 
-            website.scrape();
+            website.scrape().unwrap();
 
             let mut page_objects: Vec<_> = vec![];
 